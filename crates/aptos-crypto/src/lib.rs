@@ -12,6 +12,8 @@ pub mod hash;
 pub mod hkdf;
 pub mod multi_ed25519;
 pub mod noise;
+pub mod secp256k1;
+pub mod secp256r1;
 pub mod test_utils;
 pub mod traits;
 pub mod validatable;
@@ -0,0 +1,192 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! An abstraction of secp256k1 elliptic curve keys, as used for ECDSA by other blockchains.
+//!
+//! These are provided for cross-chain compatibility, only key generation and (de)serialization
+//! are supported.  Public keys are serialized in the uncompressed SEC1 encoding, and can be
+//! deserialized from either the compressed or uncompressed SEC1 encoding.
+//!
+//! # Examples
+//!
+//! ```
+//! use aptos_crypto::{secp256k1::*, PrivateKey, Uniform, test_utils::TEST_SEED};
+//! use rand::{rngs::StdRng, SeedableRng};
+//!
+//! let mut rng: StdRng = SeedableRng::from_seed(TEST_SEED);
+//! let private_key = Secp256k1PrivateKey::generate(&mut rng);
+//! let public_key = private_key.public_key();
+//! assert_eq!(public_key.to_bytes().len(), SECP256K1_PUBLIC_KEY_LENGTH);
+//! ```
+
+use crate::traits::{self, CryptoMaterialError, ValidCryptoMaterial, ValidCryptoMaterialStringExt};
+use aptos_crypto_derive::{DeserializeKey, SerializeKey, SilentDebug, SilentDisplay};
+use rand::{CryptoRng, RngCore};
+use std::{convert::TryFrom, fmt};
+
+pub use libsecp256k1;
+
+/// The length of the Secp256k1PrivateKey
+pub const SECP256K1_PRIVATE_KEY_LENGTH: usize = 32;
+/// The length of the Secp256k1PublicKey in the uncompressed SEC1 encoding
+pub const SECP256K1_PUBLIC_KEY_LENGTH: usize = 65;
+/// The length of the Secp256k1PublicKey in the compressed SEC1 encoding
+pub const SECP256K1_COMPRESSED_PUBLIC_KEY_LENGTH: usize = 33;
+
+/// A secp256k1 private key
+#[derive(DeserializeKey, SerializeKey, SilentDebug, SilentDisplay)]
+pub struct Secp256k1PrivateKey(libsecp256k1::SecretKey);
+
+/// A secp256k1 public key
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct Secp256k1PublicKey(libsecp256k1::PublicKey);
+
+impl Secp256k1PrivateKey {
+    /// The length of the Secp256k1PrivateKey
+    pub const LENGTH: usize = SECP256K1_PRIVATE_KEY_LENGTH;
+
+    /// Serialize a Secp256k1PrivateKey.
+    pub fn to_bytes(&self) -> [u8; SECP256K1_PRIVATE_KEY_LENGTH] {
+        self.0.serialize()
+    }
+}
+
+impl Secp256k1PublicKey {
+    /// Serialize a Secp256k1PublicKey in the uncompressed SEC1 encoding.
+    pub fn to_bytes(&self) -> [u8; SECP256K1_PUBLIC_KEY_LENGTH] {
+        self.0.serialize()
+    }
+
+    /// Serialize a Secp256k1PublicKey in the compressed SEC1 encoding.
+    pub fn to_compressed_bytes(&self) -> [u8; SECP256K1_COMPRESSED_PUBLIC_KEY_LENGTH] {
+        self.0.serialize_compressed()
+    }
+}
+
+//
+// Traits implementations
+// ======================
+//
+
+// private key part
+
+impl traits::PrivateKey for Secp256k1PrivateKey {
+    type PublicKeyMaterial = Secp256k1PublicKey;
+}
+
+impl traits::Uniform for Secp256k1PrivateKey {
+    fn generate<R>(rng: &mut R) -> Self
+    where
+        R: RngCore + CryptoRng,
+    {
+        // Nearly every 32 byte string is a valid key, so retry on the rare ones that aren't
+        loop {
+            let mut bytes = [0u8; SECP256K1_PRIVATE_KEY_LENGTH];
+            rng.fill_bytes(&mut bytes);
+            if let Ok(secret_key) = libsecp256k1::SecretKey::parse(&bytes) {
+                return Secp256k1PrivateKey(secret_key);
+            }
+        }
+    }
+}
+
+impl PartialEq for Secp256k1PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for Secp256k1PrivateKey {}
+
+impl TryFrom<&[u8]> for Secp256k1PrivateKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a Secp256k1PrivateKey, rejecting zero and scalars outside of the curve order.
+    fn try_from(bytes: &[u8]) -> Result<Secp256k1PrivateKey, CryptoMaterialError> {
+        if bytes.len() != SECP256K1_PRIVATE_KEY_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        libsecp256k1::SecretKey::parse_slice(bytes)
+            .map(Secp256k1PrivateKey)
+            .map_err(|_| CryptoMaterialError::DeserializationError)
+    }
+}
+
+impl traits::Length for Secp256k1PrivateKey {
+    fn length(&self) -> usize {
+        Self::LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Secp256k1PrivateKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+// public key part
+
+impl From<&Secp256k1PrivateKey> for Secp256k1PublicKey {
+    fn from(private_key: &Secp256k1PrivateKey) -> Self {
+        Secp256k1PublicKey(libsecp256k1::PublicKey::from_secret_key(&private_key.0))
+    }
+}
+
+impl traits::PublicKey for Secp256k1PublicKey {
+    type PrivateKeyMaterial = Secp256k1PrivateKey;
+}
+
+impl std::hash::Hash for Secp256k1PublicKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write(&self.to_bytes());
+    }
+}
+
+impl PartialEq for Secp256k1PublicKey {
+    fn eq(&self, other: &Secp256k1PublicKey) -> bool {
+        self.to_bytes()[..] == other.to_bytes()[..]
+    }
+}
+
+impl Eq for Secp256k1PublicKey {}
+
+impl TryFrom<&[u8]> for Secp256k1PublicKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a Secp256k1PublicKey from either SEC1 encoding, checking that the point is
+    /// on the curve.
+    fn try_from(bytes: &[u8]) -> Result<Secp256k1PublicKey, CryptoMaterialError> {
+        match bytes.len() {
+            SECP256K1_PUBLIC_KEY_LENGTH | SECP256K1_COMPRESSED_PUBLIC_KEY_LENGTH => {
+                libsecp256k1::PublicKey::parse_slice(bytes, None)
+                    .map(Secp256k1PublicKey)
+                    .map_err(|_| CryptoMaterialError::PointNotOnCurveError)
+            }
+            _ => Err(CryptoMaterialError::WrongLengthError),
+        }
+    }
+}
+
+impl traits::Length for Secp256k1PublicKey {
+    fn length(&self) -> usize {
+        SECP256K1_PUBLIC_KEY_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Secp256k1PublicKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl fmt::Display for Secp256k1PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()[..]))
+    }
+}
+
+impl fmt::Debug for Secp256k1PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secp256k1PublicKey({})", self)
+    }
+}
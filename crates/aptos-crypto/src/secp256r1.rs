@@ -0,0 +1,199 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! An abstraction of secp256r1 (NIST P-256) elliptic curve keys, as used for ECDSA by
+//! enterprise systems, e.g. HSMs and WebAuthn.
+//!
+//! These are provided for integrations outside of the chain, only key generation and
+//! (de)serialization are supported.  Public keys are serialized in the uncompressed SEC1
+//! encoding, and can be deserialized from either the compressed or uncompressed SEC1 encoding,
+//! like secp256k1 public keys.
+//!
+//! # Examples
+//!
+//! ```
+//! use aptos_crypto::{secp256r1::*, PrivateKey, Uniform, test_utils::TEST_SEED};
+//! use rand::{rngs::StdRng, SeedableRng};
+//!
+//! let mut rng: StdRng = SeedableRng::from_seed(TEST_SEED);
+//! let private_key = Secp256r1PrivateKey::generate(&mut rng);
+//! let public_key = private_key.public_key();
+//! assert_eq!(public_key.to_bytes().len(), SECP256R1_PUBLIC_KEY_LENGTH);
+//! ```
+
+use crate::traits::{self, CryptoMaterialError, ValidCryptoMaterial, ValidCryptoMaterialStringExt};
+use aptos_crypto_derive::{DeserializeKey, SerializeKey, SilentDebug, SilentDisplay};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use rand::{CryptoRng, RngCore};
+use std::{convert::TryFrom, fmt};
+
+pub use p256;
+
+/// The length of the Secp256r1PrivateKey
+pub const SECP256R1_PRIVATE_KEY_LENGTH: usize = 32;
+/// The length of the Secp256r1PublicKey in the uncompressed SEC1 encoding
+pub const SECP256R1_PUBLIC_KEY_LENGTH: usize = 65;
+/// The length of the Secp256r1PublicKey in the compressed SEC1 encoding
+pub const SECP256R1_COMPRESSED_PUBLIC_KEY_LENGTH: usize = 33;
+
+/// A secp256r1 private key
+#[derive(DeserializeKey, SerializeKey, SilentDebug, SilentDisplay)]
+pub struct Secp256r1PrivateKey(p256::SecretKey);
+
+/// A secp256r1 public key
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct Secp256r1PublicKey(p256::PublicKey);
+
+impl Secp256r1PrivateKey {
+    /// The length of the Secp256r1PrivateKey
+    pub const LENGTH: usize = SECP256R1_PRIVATE_KEY_LENGTH;
+
+    /// Serialize a Secp256r1PrivateKey.
+    pub fn to_bytes(&self) -> [u8; SECP256R1_PRIVATE_KEY_LENGTH] {
+        self.0.to_be_bytes().into()
+    }
+}
+
+impl Secp256r1PublicKey {
+    /// Serialize a Secp256r1PublicKey in the uncompressed SEC1 encoding.
+    pub fn to_bytes(&self) -> [u8; SECP256R1_PUBLIC_KEY_LENGTH] {
+        let mut bytes = [0u8; SECP256R1_PUBLIC_KEY_LENGTH];
+        bytes.copy_from_slice(self.0.to_encoded_point(false).as_bytes());
+        bytes
+    }
+
+    /// Serialize a Secp256r1PublicKey in the compressed SEC1 encoding.
+    pub fn to_compressed_bytes(&self) -> [u8; SECP256R1_COMPRESSED_PUBLIC_KEY_LENGTH] {
+        let mut bytes = [0u8; SECP256R1_COMPRESSED_PUBLIC_KEY_LENGTH];
+        bytes.copy_from_slice(self.0.to_encoded_point(true).as_bytes());
+        bytes
+    }
+}
+
+//
+// Traits implementations
+// ======================
+//
+
+// private key part
+
+impl traits::PrivateKey for Secp256r1PrivateKey {
+    type PublicKeyMaterial = Secp256r1PublicKey;
+}
+
+impl traits::Uniform for Secp256r1PrivateKey {
+    fn generate<R>(rng: &mut R) -> Self
+    where
+        R: RngCore + CryptoRng,
+    {
+        // Nearly every 32 byte string is a valid key, so retry on the rare ones that aren't
+        loop {
+            let mut bytes = [0u8; SECP256R1_PRIVATE_KEY_LENGTH];
+            rng.fill_bytes(&mut bytes);
+            if let Ok(secret_key) = p256::SecretKey::from_be_bytes(&bytes) {
+                return Secp256r1PrivateKey(secret_key);
+            }
+        }
+    }
+}
+
+impl PartialEq for Secp256r1PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for Secp256r1PrivateKey {}
+
+impl TryFrom<&[u8]> for Secp256r1PrivateKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a Secp256r1PrivateKey, rejecting zero and scalars outside of the curve order.
+    fn try_from(bytes: &[u8]) -> Result<Secp256r1PrivateKey, CryptoMaterialError> {
+        if bytes.len() != SECP256R1_PRIVATE_KEY_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        p256::SecretKey::from_be_bytes(bytes)
+            .map(Secp256r1PrivateKey)
+            .map_err(|_| CryptoMaterialError::DeserializationError)
+    }
+}
+
+impl traits::Length for Secp256r1PrivateKey {
+    fn length(&self) -> usize {
+        Self::LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Secp256r1PrivateKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+// public key part
+
+impl From<&Secp256r1PrivateKey> for Secp256r1PublicKey {
+    fn from(private_key: &Secp256r1PrivateKey) -> Self {
+        Secp256r1PublicKey(private_key.0.public_key())
+    }
+}
+
+impl traits::PublicKey for Secp256r1PublicKey {
+    type PrivateKeyMaterial = Secp256r1PrivateKey;
+}
+
+impl std::hash::Hash for Secp256r1PublicKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write(&self.to_bytes());
+    }
+}
+
+impl PartialEq for Secp256r1PublicKey {
+    fn eq(&self, other: &Secp256r1PublicKey) -> bool {
+        self.to_bytes()[..] == other.to_bytes()[..]
+    }
+}
+
+impl Eq for Secp256r1PublicKey {}
+
+impl TryFrom<&[u8]> for Secp256r1PublicKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a Secp256r1PublicKey from either SEC1 encoding, checking that the point is
+    /// on the curve.
+    fn try_from(bytes: &[u8]) -> Result<Secp256r1PublicKey, CryptoMaterialError> {
+        match bytes.len() {
+            SECP256R1_PUBLIC_KEY_LENGTH | SECP256R1_COMPRESSED_PUBLIC_KEY_LENGTH => {
+                p256::PublicKey::from_sec1_bytes(bytes)
+                    .map(Secp256r1PublicKey)
+                    .map_err(|_| CryptoMaterialError::PointNotOnCurveError)
+            }
+            _ => Err(CryptoMaterialError::WrongLengthError),
+        }
+    }
+}
+
+impl traits::Length for Secp256r1PublicKey {
+    fn length(&self) -> usize {
+        SECP256R1_PUBLIC_KEY_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Secp256r1PublicKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl fmt::Display for Secp256r1PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()[..]))
+    }
+}
+
+impl fmt::Debug for Secp256r1PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secp256r1PublicKey({})", self)
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    secp256k1::{
+        Secp256k1PrivateKey, Secp256k1PublicKey, SECP256K1_COMPRESSED_PUBLIC_KEY_LENGTH,
+        SECP256K1_PUBLIC_KEY_LENGTH,
+    },
+    test_utils::TEST_SEED,
+    traits::*,
+};
+use core::convert::TryFrom;
+use rand::{rngs::StdRng, SeedableRng};
+
+/// The generator point, which is the public key of the private key `1`
+const GENERATOR_X: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+const GENERATOR_Y: &str = "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+
+#[test]
+fn test_public_key_derivation() {
+    let mut private_key_bytes = [0u8; 32];
+    private_key_bytes[31] = 1;
+    let private_key = Secp256k1PrivateKey::try_from(&private_key_bytes[..]).unwrap();
+    let public_key = private_key.public_key();
+
+    assert_eq!(
+        hex::encode(public_key.to_bytes()),
+        format!("04{}{}", GENERATOR_X, GENERATOR_Y)
+    );
+    assert_eq!(
+        hex::encode(public_key.to_compressed_bytes()),
+        format!("02{}", GENERATOR_X)
+    );
+}
+
+#[test]
+fn test_serialization_round_trip() {
+    let mut rng: StdRng = SeedableRng::from_seed(TEST_SEED);
+    let private_key = Secp256k1PrivateKey::generate(&mut rng);
+    let public_key = private_key.public_key();
+
+    let private_key_bytes = ValidCryptoMaterial::to_bytes(&private_key);
+    assert_eq!(
+        Secp256k1PrivateKey::try_from(private_key_bytes.as_slice()).unwrap(),
+        private_key
+    );
+
+    // Both SEC1 encodings deserialize to the same point
+    let public_key_bytes = public_key.to_bytes();
+    let compressed_bytes = public_key.to_compressed_bytes();
+    assert_eq!(public_key_bytes.len(), SECP256K1_PUBLIC_KEY_LENGTH);
+    assert_eq!(
+        compressed_bytes.len(),
+        SECP256K1_COMPRESSED_PUBLIC_KEY_LENGTH
+    );
+    assert_eq!(
+        Secp256k1PublicKey::try_from(&public_key_bytes[..]).unwrap(),
+        public_key
+    );
+    assert_eq!(
+        Secp256k1PublicKey::try_from(&compressed_bytes[..]).unwrap(),
+        public_key
+    );
+
+    let bcs_bytes = bcs::to_bytes(&public_key).unwrap();
+    assert_eq!(
+        bcs::from_bytes::<Secp256k1PublicKey>(&bcs_bytes).unwrap(),
+        public_key
+    );
+}
+
+#[test]
+fn test_invalid_keys() {
+    // Zero isn't a valid private key
+    assert!(Secp256k1PrivateKey::try_from(&[0u8; 32][..]).is_err());
+    assert_eq!(
+        Secp256k1PrivateKey::try_from(&[1u8; 31][..]).err(),
+        Some(CryptoMaterialError::WrongLengthError)
+    );
+    assert_eq!(
+        Secp256k1PublicKey::try_from(&[4u8; 64][..]).err(),
+        Some(CryptoMaterialError::WrongLengthError)
+    );
+}
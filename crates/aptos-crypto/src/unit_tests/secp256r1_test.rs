@@ -0,0 +1,95 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    secp256r1::{
+        Secp256r1PrivateKey, Secp256r1PublicKey, SECP256R1_COMPRESSED_PUBLIC_KEY_LENGTH,
+        SECP256R1_PUBLIC_KEY_LENGTH,
+    },
+    test_utils::TEST_SEED,
+    traits::*,
+};
+use core::convert::TryFrom;
+use rand::{rngs::StdRng, SeedableRng};
+
+/// The generator point, which is the public key of the private key `1`
+const GENERATOR_X: &str = "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296";
+const GENERATOR_Y: &str = "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5";
+
+#[test]
+fn test_public_key_derivation() {
+    let mut private_key_bytes = [0u8; 32];
+    private_key_bytes[31] = 1;
+    let private_key = Secp256r1PrivateKey::try_from(&private_key_bytes[..]).unwrap();
+    let public_key = private_key.public_key();
+
+    assert_eq!(
+        hex::encode(public_key.to_bytes()),
+        format!("04{}{}", GENERATOR_X, GENERATOR_Y)
+    );
+    // The y coordinate is odd
+    assert_eq!(
+        hex::encode(public_key.to_compressed_bytes()),
+        format!("03{}", GENERATOR_X)
+    );
+}
+
+#[test]
+fn test_serialization_round_trip() {
+    let mut rng: StdRng = SeedableRng::from_seed(TEST_SEED);
+    let private_key = Secp256r1PrivateKey::generate(&mut rng);
+    let public_key = private_key.public_key();
+
+    let private_key_bytes = ValidCryptoMaterial::to_bytes(&private_key);
+    assert_eq!(
+        Secp256r1PrivateKey::try_from(private_key_bytes.as_slice()).unwrap(),
+        private_key
+    );
+
+    // Both SEC1 encodings deserialize to the same point
+    let public_key_bytes = public_key.to_bytes();
+    let compressed_bytes = public_key.to_compressed_bytes();
+    assert_eq!(public_key_bytes.len(), SECP256R1_PUBLIC_KEY_LENGTH);
+    assert_eq!(
+        compressed_bytes.len(),
+        SECP256R1_COMPRESSED_PUBLIC_KEY_LENGTH
+    );
+    assert_eq!(
+        Secp256r1PublicKey::try_from(&public_key_bytes[..]).unwrap(),
+        public_key
+    );
+    assert_eq!(
+        Secp256r1PublicKey::try_from(&compressed_bytes[..]).unwrap(),
+        public_key
+    );
+
+    let bcs_bytes = bcs::to_bytes(&public_key).unwrap();
+    assert_eq!(
+        bcs::from_bytes::<Secp256r1PublicKey>(&bcs_bytes).unwrap(),
+        public_key
+    );
+}
+
+#[test]
+fn test_invalid_keys() {
+    // Zero isn't a valid private key, nor is the curve order
+    assert!(Secp256r1PrivateKey::try_from(&[0u8; 32][..]).is_err());
+    let order =
+        hex::decode("ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551").unwrap();
+    assert!(Secp256r1PrivateKey::try_from(order.as_slice()).is_err());
+    assert_eq!(
+        Secp256r1PrivateKey::try_from(&[1u8; 31][..]).err(),
+        Some(CryptoMaterialError::WrongLengthError)
+    );
+    assert_eq!(
+        Secp256r1PublicKey::try_from(&[4u8; 64][..]).err(),
+        Some(CryptoMaterialError::WrongLengthError)
+    );
+    // A point that isn't on the curve
+    let mut off_curve = [0u8; SECP256R1_PUBLIC_KEY_LENGTH];
+    off_curve[0] = 4;
+    assert_eq!(
+        Secp256r1PublicKey::try_from(&off_curve[..]).err(),
+        Some(CryptoMaterialError::PointNotOnCurveError)
+    );
+}
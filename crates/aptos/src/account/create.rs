@@ -61,10 +61,10 @@ impl CliCommand<String> for CreateAccount {
 
 impl CreateAccount {
     async fn create_account_with_key(self, address: AccountAddress) -> CliTypedResult<()> {
-        let sender_key = self.write_options.private_key_options.extract_private_key(
-            self.encoding_options.encoding,
-            &self.profile_options.profile,
-        )?;
+        let sender_key = self
+            .write_options
+            .private_key_options
+            .extract_private_key(&self.encoding_options, &self.profile_options.profile)?;
 
         submit_transaction(
             self.write_options
@@ -42,10 +42,10 @@ impl CliCommand<TransferSummary> for TransferCoins {
     }
 
     async fn execute(self) -> CliTypedResult<TransferSummary> {
-        let sender_key = self.write_options.private_key_options.extract_private_key(
-            self.encoding_options.encoding,
-            &self.profile_options.profile,
-        )?;
+        let sender_key = self
+            .write_options
+            .private_key_options
+            .extract_private_key(&self.encoding_options, &self.profile_options.profile)?;
 
         submit_transaction(
             self.write_options
@@ -0,0 +1,97 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! An append only log of key operations, as JSON lines
+//!
+//! Each record has the time, operation, and what's known of the key, its type, public key, and
+//! file.  Private keys are never logged.
+
+use crate::common::types::CliError;
+use clap::Parser;
+use serde::Serialize;
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A logged key operation
+#[derive(Debug, Serialize)]
+pub struct AuditRecord<'a> {
+    /// When the operation finished, in seconds since the Unix epoch
+    pub timestamp_secs: u64,
+    /// Operation e.g. `generate`
+    pub operation: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_type: Option<&'a str>,
+    /// Public key in hex
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<&'a Path>,
+    /// `ok`, or `error` for a failed operation
+    pub result: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// An insertable option for logging key operations
+#[derive(Clone, Debug, Default, Parser)]
+pub struct AuditLogOptions {
+    /// File to append a JSON line to for each operation, successful or not
+    ///
+    /// Records have the time, operation, key type, and public key, and never the private key.
+    #[clap(long, parse(from_os_str))]
+    pub audit_log: Option<PathBuf>,
+}
+
+impl AuditLogOptions {
+    /// Appends a record of an operation to the `--audit-log`, if any, failed with `error`
+    ///
+    /// A record that can't be written only prints a warning, so logging never changes the
+    /// result of the operation.
+    pub fn record(
+        &self,
+        operation: &'static str,
+        key_type: Option<&str>,
+        public_key: Option<String>,
+        key_file: Option<&Path>,
+        error: Option<&CliError>,
+    ) {
+        let audit_log = match self.audit_log {
+            Some(ref audit_log) => audit_log,
+            None => return,
+        };
+        let record = AuditRecord {
+            timestamp_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            operation,
+            key_type,
+            public_key,
+            key_file,
+            result: if error.is_some() { "error" } else { "ok" },
+            error: error.map(|err| err.to_string()),
+        };
+        if let Err(err) = append_record(audit_log, &record) {
+            eprintln!(
+                "Warning: Failed to write to audit log {}: {}",
+                audit_log.display(),
+                err
+            );
+        }
+    }
+}
+
+/// Appends a record as one line, in a single write so concurrent records don't interleave
+fn append_record(audit_log: &Path, record: &AuditRecord) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(audit_log)?
+        .write_all(&line)
+}
@@ -0,0 +1,180 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Passphrase encryption of private key files
+//!
+//! An encrypted file is an envelope of a header, and the encoded key encrypted with
+//! XChaCha20-Poly1305 under a key derived from the passphrase with scrypt.  The header is
+//! authenticated along with the key, and is laid out as:
+//!
+//! ```text
+//! magic (8) | version (1) | log_n (1) | r (4) | p (4) | salt (32) | nonce (24)
+//! ```
+//!
+//! Integers are big endian.  Files of a version that isn't known are rejected rather than
+//! guessed at, so the layout after the version can change in later versions.
+
+use crate::common::types::{CliError, CliTypedResult};
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use rand::{rngs::OsRng, RngCore};
+use std::convert::TryInto;
+
+/// Marks the start of an encrypted key file
+const MAGIC: &[u8] = b"APTOSENC";
+const VERSION_1: u8 = 1;
+
+const SALT_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 24;
+const HEADER_LENGTH: usize = MAGIC.len() + 1 + 1 + 4 + 4 + SALT_LENGTH + NONCE_LENGTH;
+
+/// Default scrypt cost, the recommended parameters for interactive use
+const DEFAULT_LOG_N: u8 = 15;
+const DEFAULT_R: u32 = 8;
+const DEFAULT_P: u32 = 1;
+/// Limits on the cost of a file, so a malicious file can't exhaust memory or CPU
+///
+/// scrypt takes `128 * r * 2^log_n` bytes of memory and `p` times as long, so `r`, `p` and
+/// `r * p` are capped as well as `log_n`.
+pub const MAX_LOG_N: u8 = 20;
+pub const MAX_R: u32 = 16;
+pub const MAX_P: u32 = 16;
+pub const MAX_R_TIMES_P: u32 = 64;
+
+/// Checks if the bytes of a file are an encrypted envelope
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Encrypts `plaintext` with `passphrase` using the default scrypt cost
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> CliTypedResult<Vec<u8>> {
+    encrypt_with_cost(plaintext, passphrase, DEFAULT_LOG_N, DEFAULT_R, DEFAULT_P)
+}
+
+/// Encrypts `plaintext` with `passphrase` using the given scrypt cost
+pub fn encrypt_with_cost(
+    plaintext: &[u8],
+    passphrase: &str,
+    log_n: u8,
+    r: u32,
+    p: u32,
+) -> CliTypedResult<Vec<u8>> {
+    // A file that couldn't be decrypted again is never written
+    check_cost(log_n, r, p).map_err(CliError::CommandArgumentError)?;
+    let mut salt = [0u8; SALT_LENGTH];
+    let mut nonce = [0u8; NONCE_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut envelope = Vec::with_capacity(HEADER_LENGTH + plaintext.len() + 16);
+    envelope.extend_from_slice(MAGIC);
+    envelope.push(VERSION_1);
+    envelope.push(log_n);
+    envelope.extend_from_slice(&r.to_be_bytes());
+    envelope.extend_from_slice(&p.to_be_bytes());
+    envelope.extend_from_slice(&salt);
+    envelope.extend_from_slice(&nonce);
+
+    let cipher = cipher(passphrase, &salt, log_n, r, p)?;
+    let ciphertext = cipher
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: &envelope,
+            },
+        )
+        .map_err(|_| CliError::UnexpectedError("Failed to encrypt key".to_string()))?;
+    envelope.extend_from_slice(&ciphertext);
+    Ok(envelope)
+}
+
+/// Decrypts an envelope made by `encrypt`, failing with `CliError::DecryptionError` if the
+/// passphrase is wrong
+pub fn decrypt(name: &str, envelope: &[u8], passphrase: &str) -> CliTypedResult<Vec<u8>> {
+    if !is_encrypted(envelope) {
+        return Err(CliError::UnableToReadFile(
+            name.to_string(),
+            "not an encrypted key".to_string(),
+        ));
+    }
+    match envelope.get(MAGIC.len()) {
+        Some(&VERSION_1) => (),
+        Some(version) => {
+            return Err(CliError::UnableToReadFile(
+                name.to_string(),
+                format!("unsupported encrypted key version {}", version),
+            ))
+        }
+        None => return Err(truncated(name)),
+    }
+    if envelope.len() < HEADER_LENGTH {
+        return Err(truncated(name));
+    }
+
+    let (header, ciphertext) = envelope.split_at(HEADER_LENGTH);
+    let mut rest = &header[MAGIC.len() + 1..];
+    let log_n = rest[0];
+    rest = &rest[1..];
+    let r = u32::from_be_bytes(rest[..4].try_into().unwrap());
+    let p = u32::from_be_bytes(rest[4..8].try_into().unwrap());
+    rest = &rest[8..];
+    let (salt, nonce) = rest.split_at(SALT_LENGTH);
+
+    check_cost(log_n, r, p).map_err(|err| CliError::UnableToReadFile(name.to_string(), err))?;
+
+    cipher(passphrase, salt, log_n, r, p)?
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| CliError::DecryptionError(name.to_string()))
+}
+
+/// Checks a scrypt cost is within the limits, before any memory is allocated for it
+fn check_cost(log_n: u8, r: u32, p: u32) -> Result<(), String> {
+    let r_times_p = u64::from(r) * u64::from(p);
+    for (what, value, limit) in [
+        ("cost", u64::from(log_n), u64::from(MAX_LOG_N)),
+        ("block size", u64::from(r), u64::from(MAX_R)),
+        ("parallelism", u64::from(p), u64::from(MAX_P)),
+        (
+            "block size times parallelism",
+            r_times_p,
+            u64::from(MAX_R_TIMES_P),
+        ),
+    ] {
+        if value > limit {
+            return Err(format!(
+                "scrypt {} {} is larger than the limit {}",
+                what, value, limit
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Derives the cipher for a passphrase with scrypt
+fn cipher(
+    passphrase: &str,
+    salt: &[u8],
+    log_n: u8,
+    r: u32,
+    p: u32,
+) -> CliTypedResult<XChaCha20Poly1305> {
+    let params = scrypt::Params::new(log_n, r, p)
+        .map_err(|err| CliError::UnexpectedError(format!("Invalid scrypt parameters {}", err)))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut key)
+        .map_err(|err| CliError::UnexpectedError(format!("Failed to derive key {}", err)))?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn truncated(name: &str) -> CliError {
+    CliError::UnableToReadFile(name.to_string(), "encrypted key is truncated".to_string())
+}
@@ -122,7 +122,7 @@ impl CliCommand<()> for InitTool {
         // Private key
         let private_key = if let Some(private_key) = self
             .private_key_options
-            .extract_private_key_cli(self.encoding_options.encoding)?
+            .extract_private_key_cli(&self.encoding_options)?
         {
             eprintln!("Using command line argument for private key");
             private_key
@@ -0,0 +1,301 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Where private keys are stored, as files or in the OS keyring
+//!
+//! A store only holds the encoded, and possibly encrypted, private key.  Public keys aren't
+//! secret, so they're always saved to files.
+
+use crate::common::{
+    types::{CliError, CliTypedResult, PromptOptions},
+    utils::{
+        check_if_file_exists, prompt_yes_with_override, read_from_file_or_stdin,
+        write_to_user_only_file,
+    },
+};
+use clap::{ArgEnum, Parser};
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use zeroize::Zeroizing;
+
+/// Keyring service that keys are stored under by default
+pub const DEFAULT_KEYRING_SERVICE: &str = "aptos";
+
+/// A place to store an encoded private key
+pub trait KeyStore {
+    /// Describes where the key is stored, for messages and outputs
+    fn location(&self) -> String;
+
+    /// File the key is stored in, if it's stored in a file
+    fn path(&self) -> Option<&Path> {
+        None
+    }
+
+    /// Whether a key is already stored
+    fn exists(&self) -> CliTypedResult<bool>;
+
+    /// Stores the encoded key, replacing any key already stored
+    fn save(&self, name: &'static str, encoded_key: &[u8]) -> CliTypedResult<()>;
+
+    /// Loads the encoded key
+    fn load(&self) -> CliTypedResult<Vec<u8>>;
+
+    /// Checks a stored key can be replaced, prompting if there's already a key
+    fn check_overwrite(&self, prompt_options: PromptOptions) -> CliTypedResult<()>;
+}
+
+/// Stores a key in a file readable only by the user
+pub struct FileKeyStore {
+    path: PathBuf,
+}
+
+impl FileKeyStore {
+    pub fn new(path: PathBuf) -> FileKeyStore {
+        FileKeyStore { path }
+    }
+}
+
+impl KeyStore for FileKeyStore {
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn path(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+
+    fn exists(&self) -> CliTypedResult<bool> {
+        Ok(self.path.exists())
+    }
+
+    fn save(&self, name: &'static str, encoded_key: &[u8]) -> CliTypedResult<()> {
+        write_to_user_only_file(&self.path, name, encoded_key)
+    }
+
+    fn load(&self) -> CliTypedResult<Vec<u8>> {
+        read_from_file_or_stdin(&self.path)
+    }
+
+    fn check_overwrite(&self, prompt_options: PromptOptions) -> CliTypedResult<()> {
+        check_if_file_exists(&self.path, prompt_options)
+    }
+}
+
+/// Prefix of a keyring password holding a binary key, e.g. an encrypted or BCS key, in base64
+pub const KEYRING_BASE64_PREFIX: &str = "base64:";
+
+/// Stores a key in the OS keyring, as the password of an account of a service
+///
+/// The keyring only holds text, so a binary key is stored in base64 after
+/// [`KEYRING_BASE64_PREFIX`].
+pub struct KeyringKeyStore {
+    service: String,
+    account: String,
+}
+
+impl KeyringKeyStore {
+    pub fn new(service: String, account: String) -> KeyringKeyStore {
+        KeyringKeyStore { service, account }
+    }
+
+    fn entry(&self) -> keyring::Entry {
+        keyring::Entry::new(&self.service, &self.account)
+    }
+
+    /// The password a key is stored as, the key itself if it's text
+    pub fn encode_password(encoded_key: &[u8]) -> Zeroizing<String> {
+        match std::str::from_utf8(encoded_key) {
+            Ok(text) if !text.starts_with(KEYRING_BASE64_PREFIX) => {
+                Zeroizing::new(text.to_string())
+            }
+            _ => Zeroizing::new(format!(
+                "{}{}",
+                KEYRING_BASE64_PREFIX,
+                base64::encode(encoded_key)
+            )),
+        }
+    }
+
+    /// The key stored as `password`, undoing [`KeyringKeyStore::encode_password`]
+    pub fn decode_password(&self, password: &str) -> CliTypedResult<Vec<u8>> {
+        match password.strip_prefix(KEYRING_BASE64_PREFIX) {
+            Some(encoded_key) => base64::decode(encoded_key).map_err(|err| {
+                CliError::KeyStoreError(format!(
+                    "The key in {} isn't valid base64: {}",
+                    self.location(),
+                    err
+                ))
+            }),
+            None => Ok(password.as_bytes().to_vec()),
+        }
+    }
+
+    /// Maps a keyring error, telling an unavailable keyring apart from a missing key
+    fn error(&self, err: keyring::Error) -> CliError {
+        match err {
+            keyring::Error::NoEntry => {
+                CliError::KeyStoreError(format!("No key is stored in {}", self.location()))
+            }
+            keyring::Error::NoStorageAccess(err) | keyring::Error::PlatformFailure(err) => {
+                CliError::KeyStoreError(format!(
+                    "The OS keyring isn't available ({}), use --key-store file instead",
+                    err
+                ))
+            }
+            err => CliError::KeyStoreError(format!("{}: {}", self.location(), err)),
+        }
+    }
+}
+
+impl KeyStore for KeyringKeyStore {
+    fn location(&self) -> String {
+        format!("keyring:{}/{}", self.service, self.account)
+    }
+
+    fn exists(&self) -> CliTypedResult<bool> {
+        match self.entry().get_password() {
+            Ok(_) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(err) => Err(self.error(err)),
+        }
+    }
+
+    fn save(&self, _name: &'static str, encoded_key: &[u8]) -> CliTypedResult<()> {
+        self.entry()
+            .set_password(&Self::encode_password(encoded_key))
+            .map_err(|err| self.error(err))
+    }
+
+    fn load(&self) -> CliTypedResult<Vec<u8>> {
+        let password = Zeroizing::new(self.entry().get_password().map_err(|err| self.error(err))?);
+        self.decode_password(&password)
+    }
+
+    fn check_overwrite(&self, prompt_options: PromptOptions) -> CliTypedResult<()> {
+        if self.exists()? {
+            prompt_options.check_no_overwrite(&self.location())?;
+            prompt_yes_with_override(
+                &format!(
+                    "{} already exists, are you sure you want to overwrite it?",
+                    self.location()
+                ),
+                prompt_options,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Stores a key in memory, for tests and for programs embedding the key tool without a disk
+#[derive(Default)]
+pub struct MemoryKeyStore {
+    encoded_key: RefCell<Option<Zeroizing<Vec<u8>>>>,
+}
+
+impl KeyStore for MemoryKeyStore {
+    fn location(&self) -> String {
+        "memory".to_string()
+    }
+
+    fn exists(&self) -> CliTypedResult<bool> {
+        Ok(self.encoded_key.borrow().is_some())
+    }
+
+    fn save(&self, _name: &'static str, encoded_key: &[u8]) -> CliTypedResult<()> {
+        *self.encoded_key.borrow_mut() = Some(Zeroizing::new(encoded_key.to_vec()));
+        Ok(())
+    }
+
+    fn load(&self) -> CliTypedResult<Vec<u8>> {
+        self.encoded_key
+            .borrow()
+            .as_ref()
+            .map(|encoded_key| encoded_key.to_vec())
+            .ok_or_else(|| CliError::KeyStoreError("No key is stored in memory".to_string()))
+    }
+
+    fn check_overwrite(&self, prompt_options: PromptOptions) -> CliTypedResult<()> {
+        if self.exists()? {
+            prompt_options.check_no_overwrite(&self.location())?;
+            prompt_yes_with_override(
+                "The key in memory already exists, are you sure you want to overwrite it?",
+                prompt_options,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Types of stores for private keys
+#[derive(ArgEnum, Clone, Copy, Debug)]
+pub enum KeyStoreType {
+    /// A file readable only by the user
+    File,
+    /// The OS keyring
+    Keyring,
+}
+
+impl FromStr for KeyStoreType {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "file" => Ok(KeyStoreType::File),
+            "keyring" => Ok(KeyStoreType::Keyring),
+            _ => Err("Invalid key store type"),
+        }
+    }
+}
+
+/// An insertable option for choosing where private keys are stored
+#[derive(Clone, Debug, Parser)]
+pub struct KeyStoreOptions {
+    /// Where the private key is stored: `file` or `keyring`
+    #[clap(long, default_value = "file")]
+    pub key_store: KeyStoreType,
+    /// Keyring service the private key is stored under, with `--key-store keyring`
+    #[clap(long, default_value = DEFAULT_KEYRING_SERVICE)]
+    pub keyring_service: String,
+    /// Keyring account the private key is stored as, with `--key-store keyring`
+    ///
+    /// Defaults to the private key file name.
+    #[clap(long)]
+    pub keyring_account: Option<String>,
+}
+
+impl Default for KeyStoreOptions {
+    /// Stores keys in files, as without any `--key-store` option
+    fn default() -> Self {
+        KeyStoreOptions {
+            key_store: KeyStoreType::File,
+            keyring_service: DEFAULT_KEYRING_SERVICE.to_string(),
+            keyring_account: None,
+        }
+    }
+}
+
+impl KeyStoreOptions {
+    /// The store for a private key, which is the file `path` unless it's in the keyring
+    pub fn key_store(&self, path: PathBuf) -> Box<dyn KeyStore> {
+        match self.key_store {
+            KeyStoreType::File => Box::new(FileKeyStore::new(path)),
+            KeyStoreType::Keyring => Box::new(self.keyring(path.display().to_string())),
+        }
+    }
+
+    /// Whether the private key is in the keyring, with `--key-store keyring`
+    pub fn is_keyring(&self) -> bool {
+        matches!(self.key_store, KeyStoreType::Keyring)
+    }
+
+    /// The keyring entry of a private key, as `--keyring-account` or else `default_account`
+    pub fn keyring(&self, default_account: String) -> KeyringKeyStore {
+        KeyringKeyStore::new(
+            self.keyring_service.clone(),
+            self.keyring_account.clone().unwrap_or(default_account),
+        )
+    }
+}
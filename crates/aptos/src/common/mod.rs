@@ -1,6 +1,12 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod audit;
+pub mod encryption;
 pub mod init;
+pub mod key_store;
 pub mod types;
 pub mod utils;
+
+#[cfg(test)]
+mod tests;
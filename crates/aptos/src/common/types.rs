@@ -3,22 +3,40 @@
 
 use crate::{
     common::{
+        encryption,
         init::{DEFAULT_FAUCET_URL, DEFAULT_REST_URL},
+        key_store::{KeyStore, KeyStoreOptions},
         utils::{
-            check_if_file_exists, read_from_file, to_common_result, to_common_success_result,
-            write_to_file, write_to_file_with_opts, write_to_user_only_file,
+            self, append_file_extension, check_if_file_exists, is_stdout_path, read_bounded,
+            read_from_file, read_from_file_or_stdin, read_new_passphrase, read_passphrase,
+            to_common_result, to_common_success_result, write_to_file, write_to_file_or_stdout,
+            write_to_file_with_opts, write_to_public_file, write_to_user_only_file, MAX_KEY_SIZE,
+            STDIN_PATH, STDOUT_PATH,
         },
     },
     genesis::git::from_yaml,
 };
 use aptos_crypto::{
-    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
-    x25519, PrivateKey, ValidCryptoMaterial, ValidCryptoMaterialStringExt,
+    ed25519::{
+        Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature, ED25519_PRIVATE_KEY_LENGTH,
+        ED25519_PUBLIC_KEY_LENGTH, ED25519_SIGNATURE_LENGTH,
+    },
+    multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
+    secp256k1::{
+        Secp256k1PrivateKey, Secp256k1PublicKey, SECP256K1_COMPRESSED_PUBLIC_KEY_LENGTH,
+        SECP256K1_PRIVATE_KEY_LENGTH, SECP256K1_PUBLIC_KEY_LENGTH,
+    },
+    secp256r1::{
+        Secp256r1PrivateKey, Secp256r1PublicKey, SECP256R1_COMPRESSED_PUBLIC_KEY_LENGTH,
+        SECP256R1_PRIVATE_KEY_LENGTH, SECP256R1_PUBLIC_KEY_LENGTH,
+    },
+    x25519, PrivateKey, ValidCryptoMaterial,
 };
 use aptos_logger::debug;
 use aptos_rest_client::{aptos_api_types::WriteSetChange, Client, Transaction};
 use aptos_types::{chain_id::ChainId, transaction::authenticator::AuthenticationKey};
 use async_trait::async_trait;
+use bech32::{FromBase32, ToBase32};
 use clap::{ArgEnum, Parser};
 use move_deps::move_core_types::account_address::AccountAddress;
 use serde::{Deserialize, Serialize};
@@ -28,14 +46,31 @@ use std::{
     collections::{BTreeMap, HashMap},
     fmt::Debug,
     fs::OpenOptions,
+    io::{Read, Write},
     path::{Path, PathBuf},
     str::FromStr,
     time::Instant,
 };
 use thiserror::Error;
+use zeroize::{Zeroize, Zeroizing};
 
 /// A common result to be returned to users
-pub type CliResult = Result<String, String>;
+pub type CliResult = Result<String, CliFailure>;
+
+/// The output of a failed command, and the process exit code to fail with
+#[derive(Debug)]
+pub struct CliFailure {
+    pub output: String,
+    pub exit_code: i32,
+    /// The output is a JSON error, printed to stderr rather than stdout
+    pub to_stderr: bool,
+}
+
+impl std::fmt::Display for CliFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.output)
+    }
+}
 
 /// A common result to remove need for typing `Result<T, CliError>`
 pub type CliTypedResult<T> = Result<T, CliError>;
@@ -49,14 +84,24 @@ pub enum CliError {
     ApiError(String),
     #[error("Error (de)serializing '{0}': {1}")]
     BCS(&'static str, #[source] bcs::Error),
+    #[error("Batch verification failed: {0}")]
+    BatchVerifyFailed(serde_json::Value),
+    #[error("Checksum mismatch: {0}")]
+    ChecksumMismatch(String),
     #[error("Invalid arguments: {0}")]
     CommandArgumentError(String),
     #[error("Unable to load config: {0} {1}")]
     ConfigLoadError(String, String),
     #[error("Unable to find config {0}, have you run `aptos init`?")]
     ConfigNotFoundError(String),
+    #[error("Unable to decrypt '{0}', the passphrase is wrong or the file is corrupted")]
+    DecryptionError(String),
     #[error("Error accessing '{0}': {1}")]
     IO(String, #[source] std::io::Error),
+    #[error("Key store error: {0}")]
+    KeyStoreError(String),
+    #[error("Keys differ: {0}")]
+    KeysDiffer(String),
     #[error("Move compilation failed: {0}")]
     MoveCompilationError(String),
     #[error("Move unit tests failed: {0}")]
@@ -71,14 +116,24 @@ pub enum CliError {
 
 impl CliError {
     pub fn to_str(&self) -> &'static str {
+        self.kind()
+    }
+
+    /// Kind of the error, as named in JSON errors for tools wrapping the CLI
+    pub fn kind(&self) -> &'static str {
         match self {
             CliError::AbortedError => "AbortedError",
             CliError::ApiError(_) => "ApiError",
             CliError::BCS(_, _) => "BCS",
+            CliError::BatchVerifyFailed(_) => "BatchVerifyFailed",
+            CliError::ChecksumMismatch(_) => "ChecksumMismatch",
             CliError::CommandArgumentError(_) => "CommandArgumentError",
             CliError::ConfigLoadError(_, _) => "ConfigLoadError",
             CliError::ConfigNotFoundError(_) => "ConfigNotFoundError",
+            CliError::DecryptionError(_) => "DecryptionError",
             CliError::IO(_, _) => "IO",
+            CliError::KeyStoreError(_) => "KeyStoreError",
+            CliError::KeysDiffer(_) => "KeysDiffer",
             CliError::MoveCompilationError(_) => "MoveCompilationError",
             CliError::MoveTestError(_) => "MoveTestError",
             CliError::UnableToParse(_, _) => "UnableToParse",
@@ -86,6 +141,44 @@ impl CliError {
             CliError::UnexpectedError(_) => "UnexpectedError",
         }
     }
+
+    /// Process exit code for the error, so scripts can tell failures apart
+    ///
+    /// | Code | Errors                                                     |
+    /// |------|------------------------------------------------------------|
+    /// | 1    | `UnexpectedError`, `MoveCompilationError`, `MoveTestError` |
+    /// | 1    | `KeysDiffer`, as `diff` exits when its inputs differ       |
+    /// | 1    | `BatchVerifyFailed`, as `verify` fails a bad signature     |
+    /// | 2    | `CommandArgumentError`, `UnableToParse`, `BCS`             |
+    /// | 65   | `ChecksumMismatch`, the data is corrupted                  |
+    /// | 66   | `UnableToReadFile`, e.g. the file doesn't exist            |
+    /// | 69   | `ApiError`, `KeyStoreError`                                |
+    /// | 74   | `IO`                                                       |
+    /// | 77   | `DecryptionError`                                          |
+    /// | 78   | `ConfigLoadError`, `ConfigNotFoundError`                   |
+    /// | 130  | `AbortedError`, e.g. declining to overwrite a file         |
+    ///
+    /// 2 is the code for bad arguments, as clap uses, and the others from 64 up are from
+    /// `sysexits.h`.  130 is the code of a command interrupted with Ctrl-C.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::UnexpectedError(_)
+            | CliError::MoveCompilationError(_)
+            | CliError::MoveTestError(_)
+            | CliError::KeysDiffer(_)
+            | CliError::BatchVerifyFailed(_) => 1,
+            CliError::CommandArgumentError(_)
+            | CliError::UnableToParse(_, _)
+            | CliError::BCS(_, _) => 2,
+            CliError::ChecksumMismatch(_) => 65,
+            CliError::UnableToReadFile(_, _) => 66,
+            CliError::ApiError(_) | CliError::KeyStoreError(_) => 69,
+            CliError::IO(_, _) => 74,
+            CliError::DecryptionError(_) => 77,
+            CliError::ConfigLoadError(_, _) | CliError::ConfigNotFoundError(_) => 78,
+            CliError::AbortedError => 130,
+        }
+    }
 }
 
 impl From<aptos_config::config::Error> for CliError {
@@ -246,26 +339,141 @@ impl CliConfig {
 }
 
 /// Types of Keys used by the blockchain
-#[derive(ArgEnum, Clone, Copy, Debug)]
+///
+/// Only `ed25519` keys can authenticate accounts on-chain, and only `x25519` keys identify
+/// network peers.  The ECDSA curves are for off-chain signing and integrations.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum KeyType {
     /// Ed25519 key used for signing
     Ed25519,
     /// X25519 key used for network handshakes and identity
     X25519,
+    /// Secp256k1 ECDSA key used for compatibility with other blockchains
+    Secp256k1,
+    /// Secp256r1 (P-256) ECDSA key used for compatibility with hardware keys and WebAuthn
+    Secp256r1,
+}
+
+impl KeyType {
+    /// All key types, in the order they're listed in messages
+    pub const ALL: &'static [KeyType] = &[
+        KeyType::Ed25519,
+        KeyType::X25519,
+        KeyType::Secp256k1,
+        KeyType::Secp256r1,
+    ];
+
+    /// Name of the key type, as used in messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyType::Ed25519 => "ed25519",
+            KeyType::X25519 => "x25519",
+            KeyType::Secp256k1 => "secp256k1",
+            KeyType::Secp256r1 => "secp256r1",
+        }
+    }
+
+    /// Curve of the key type, as named in algorithm tags
+    pub fn curve(&self) -> &'static str {
+        match self {
+            KeyType::Ed25519 => "edwards25519",
+            KeyType::X25519 => "curve25519",
+            KeyType::Secp256k1 => "secp256k1",
+            KeyType::Secp256r1 => "p256",
+        }
+    }
+
+    /// Scheme the key type is used in, as named in algorithm tags
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            KeyType::Ed25519 => "eddsa",
+            KeyType::X25519 => "ecdh",
+            KeyType::Secp256k1 | KeyType::Secp256r1 => "ecdsa",
+        }
+    }
+}
+
+impl std::fmt::Display for KeyType {
+    /// Shows the key type by name, which parses back to the same key type
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 impl FromStr for KeyType {
-    type Err = &'static str;
+    type Err = String;
 
+    /// Parses a key type by name, in any case
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "ed25519" => Ok(KeyType::Ed25519),
-            "x25519" => Ok(KeyType::X25519),
-            _ => Err("Invalid key type"),
+        parse_by_name(s, "key type", KeyType::ALL, KeyType::name)
+    }
+}
+
+/// A key type to load a key as, or `auto` to try each key type in the order of `KeyType::ALL`
+///
+/// Every 32 bytes are an `ed25519` private key, so `auto` never detects private keys of the
+/// other key types, and reports them as ambiguous.  It can usually tell public keys apart, as
+/// only some bytes are `ed25519` points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyTypeSelection {
+    Auto,
+    Only(KeyType),
+}
+
+impl KeyTypeSelection {
+    /// Key types to try loading a key as, in order
+    pub fn candidates(&self) -> &[KeyType] {
+        match self {
+            KeyTypeSelection::Auto => KeyType::ALL,
+            KeyTypeSelection::Only(key_type) => std::slice::from_ref(key_type),
+        }
+    }
+}
+
+impl std::fmt::Display for KeyTypeSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyTypeSelection::Auto => f.write_str("auto"),
+            KeyTypeSelection::Only(key_type) => key_type.fmt(f),
+        }
+    }
+}
+
+impl FromStr for KeyTypeSelection {
+    type Err = String;
+
+    /// Parses `auto` or a key type by name, in any case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(KeyTypeSelection::Auto);
         }
+        s.parse()
+            .map(KeyTypeSelection::Only)
+            .map_err(|err| format!("{}, or auto", err))
     }
 }
 
+/// Finds the value named `s` in any case, or fails listing the accepted names
+pub(crate) fn parse_by_name<T: Copy>(
+    s: &str,
+    kind: &str,
+    values: &[T],
+    name: fn(&T) -> &'static str,
+) -> Result<T, String> {
+    values
+        .iter()
+        .find(|value| name(value).eq_ignore_ascii_case(s))
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "Invalid {} '{}', expected one of: {}",
+                kind,
+                s,
+                values.iter().map(name).collect::<Vec<_>>().join(", ")
+            )
+        })
+}
+
 #[derive(Debug, Parser)]
 pub struct ProfileOptions {
     /// Profile to use from config
@@ -273,8 +481,14 @@ pub struct ProfileOptions {
     pub profile: String,
 }
 
+/// Longest ULEB128 length prefix of a BCS serialized sequence
+const MAX_ULEB128_LENGTH: usize = 5;
+
+/// Number of bytes hex encoded at a time when streaming keys to a writer
+const ENCODE_CHUNK_SIZE: usize = 512;
+
 /// Types of encodings used by the blockchain
-#[derive(ArgEnum, Clone, Copy, Debug)]
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EncodingType {
     /// Binary Canonical Serialization
     BCS,
@@ -282,65 +496,954 @@ pub enum EncodingType {
     Hex,
     /// Base 64 encoded
     Base64,
+    /// URL and file name safe base 64 encoded, without padding
+    Base64Url,
+    /// The DER of `ed25519` and `x25519` keys, or the raw bytes of other keys, base 64 encoded in
+    /// PEM armor labelled as a private or public key
+    Pem,
+    /// PKCS#8 DER for private keys, and SubjectPublicKeyInfo DER for public keys
+    Der,
+    /// The raw bytes of the key, with no text encoding or BCS length prefix
+    Raw,
+    /// Bech32 with a human readable prefix and a checksum, e.g. aptkey1...
+    Bech32,
+    /// Detected when reading, trying hex, then base 64, then BCS
+    Auto,
+}
+
+/// Encodings tried in order by `EncodingType::Auto`, the stricter text encodings first
+///
+/// Hex is tried before base 64, as hex is also valid base 64 but not the other way around.
+const AUTO_ENCODINGS: &[EncodingType] =
+    &[EncodingType::Hex, EncodingType::Base64, EncodingType::BCS];
+
+/// Human readable part of `bech32` encoded keys, unless another is given with `--bech32-hrp`
+pub const DEFAULT_BECH32_HRP: &str = "aptkey";
+
+/// How a type of key is labelled and structured in the PEM and DER encodings
+pub trait KeyFormat {
+    /// Label of the key in PEM armor, telling private keys and public keys apart
+    const PEM_LABEL: &'static str;
+    /// DER encoding of the PKCS#8 or SubjectPublicKeyInfo structure up to the raw key bytes,
+    /// if the key can be DER encoded
+    const DER_PREFIX: Option<&'static [u8]>;
+    /// Lengths the raw bytes of the key can have, or none for a key without a fixed length
+    const LENGTHS: &'static [usize] = &[];
+    /// Type of the key, if it's a single key of one of the `KeyType`s
+    const KEY_TYPE: Option<KeyType> = None;
+    /// Whether the key is a private key, to be saved readable only by the User
+    const PRIVATE: bool;
+
+    /// Raw bytes of the key to encode, which are only in another form for keys that have
+    /// several, e.g. compressed `secp256k1` public keys
+    fn raw_bytes(&self, _secp_format: SecpFormat) -> Vec<u8>
+    where
+        Self: ValidCryptoMaterial,
+    {
+        self.to_bytes()
+    }
+}
+
+/// Describes the type of a `Key` for logs, e.g. `ed25519 private key`, and never the key itself
+pub(crate) fn describe_key<Key: KeyFormat>() -> String {
+    let part = if Key::PRIVATE {
+        "private key"
+    } else {
+        "public key"
+    };
+    match Key::KEY_TYPE {
+        Some(key_type) => format!("{} {}", key_type, part),
+        None => part.to_string(),
+    }
+}
+
+const PRIVATE_KEY_PEM_LABEL: &str = "PRIVATE KEY";
+const PUBLIC_KEY_PEM_LABEL: &str = "PUBLIC KEY";
+
+/// PKCS#8 `OneAsymmetricKey` of an ed25519 private key, as described in RFC 8410
+const ED25519_PRIVATE_KEY_DER_PREFIX: &[u8] = &[
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+/// `SubjectPublicKeyInfo` of an ed25519 public key, as described in RFC 8410
+const ED25519_PUBLIC_KEY_DER_PREFIX: &[u8] = &[
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+/// PKCS#8 `OneAsymmetricKey` of a x25519 private key, as described in RFC 8410
+const X25519_PRIVATE_KEY_DER_PREFIX: &[u8] = &[
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x6e, 0x04, 0x22, 0x04, 0x20,
+];
+/// `SubjectPublicKeyInfo` of a x25519 public key, as described in RFC 8410
+const X25519_PUBLIC_KEY_DER_PREFIX: &[u8] = &[
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x6e, 0x03, 0x21, 0x00,
+];
+/// Every DER prefix, for decoding DER without knowing the type of key
+const DER_PREFIXES: &[&[u8]] = &[
+    ED25519_PRIVATE_KEY_DER_PREFIX,
+    ED25519_PUBLIC_KEY_DER_PREFIX,
+    X25519_PRIVATE_KEY_DER_PREFIX,
+    X25519_PUBLIC_KEY_DER_PREFIX,
+];
+/// Keys with a DER encoding are all 32 bytes
+const DER_KEY_LENGTH: usize = 32;
+
+impl KeyFormat for Ed25519PrivateKey {
+    const PEM_LABEL: &'static str = PRIVATE_KEY_PEM_LABEL;
+    const DER_PREFIX: Option<&'static [u8]> = Some(ED25519_PRIVATE_KEY_DER_PREFIX);
+    const LENGTHS: &'static [usize] = &[ED25519_PRIVATE_KEY_LENGTH];
+    const KEY_TYPE: Option<KeyType> = Some(KeyType::Ed25519);
+    const PRIVATE: bool = true;
+}
+
+impl KeyFormat for Ed25519PublicKey {
+    const PEM_LABEL: &'static str = PUBLIC_KEY_PEM_LABEL;
+    const DER_PREFIX: Option<&'static [u8]> = Some(ED25519_PUBLIC_KEY_DER_PREFIX);
+    const LENGTHS: &'static [usize] = &[ED25519_PUBLIC_KEY_LENGTH];
+    const KEY_TYPE: Option<KeyType> = Some(KeyType::Ed25519);
+    const PRIVATE: bool = false;
+}
+
+impl KeyFormat for Ed25519Signature {
+    const PEM_LABEL: &'static str = "SIGNATURE";
+    const DER_PREFIX: Option<&'static [u8]> = None;
+    const LENGTHS: &'static [usize] = &[ED25519_SIGNATURE_LENGTH];
+    const PRIVATE: bool = false;
+}
+
+impl KeyFormat for MultiEd25519PublicKey {
+    const PEM_LABEL: &'static str = "MULTI-ED25519 PUBLIC KEY";
+    const DER_PREFIX: Option<&'static [u8]> = None;
+    const PRIVATE: bool = false;
+}
+
+impl KeyFormat for MultiEd25519Signature {
+    const PEM_LABEL: &'static str = "MULTI-ED25519 SIGNATURE";
+    const DER_PREFIX: Option<&'static [u8]> = None;
+    const PRIVATE: bool = false;
+}
+
+impl KeyFormat for x25519::PrivateKey {
+    const PEM_LABEL: &'static str = PRIVATE_KEY_PEM_LABEL;
+    const DER_PREFIX: Option<&'static [u8]> = Some(X25519_PRIVATE_KEY_DER_PREFIX);
+    const LENGTHS: &'static [usize] = &[x25519::PRIVATE_KEY_SIZE];
+    const KEY_TYPE: Option<KeyType> = Some(KeyType::X25519);
+    const PRIVATE: bool = true;
+}
+
+impl KeyFormat for x25519::PublicKey {
+    const PEM_LABEL: &'static str = PUBLIC_KEY_PEM_LABEL;
+    const DER_PREFIX: Option<&'static [u8]> = Some(X25519_PUBLIC_KEY_DER_PREFIX);
+    const LENGTHS: &'static [usize] = &[x25519::PUBLIC_KEY_SIZE];
+    const KEY_TYPE: Option<KeyType> = Some(KeyType::X25519);
+    const PRIVATE: bool = false;
+}
+
+impl KeyFormat for Secp256k1PrivateKey {
+    const PEM_LABEL: &'static str = PRIVATE_KEY_PEM_LABEL;
+    const DER_PREFIX: Option<&'static [u8]> = None;
+    const LENGTHS: &'static [usize] = &[SECP256K1_PRIVATE_KEY_LENGTH];
+    const KEY_TYPE: Option<KeyType> = Some(KeyType::Secp256k1);
+    const PRIVATE: bool = true;
+}
+
+/// Either SEC1 form is decoded to the same key
+impl KeyFormat for Secp256k1PublicKey {
+    const PEM_LABEL: &'static str = PUBLIC_KEY_PEM_LABEL;
+    const DER_PREFIX: Option<&'static [u8]> = None;
+    const LENGTHS: &'static [usize] = &[
+        SECP256K1_COMPRESSED_PUBLIC_KEY_LENGTH,
+        SECP256K1_PUBLIC_KEY_LENGTH,
+    ];
+    const KEY_TYPE: Option<KeyType> = Some(KeyType::Secp256k1);
+    const PRIVATE: bool = false;
+
+    fn raw_bytes(&self, secp_format: SecpFormat) -> Vec<u8> {
+        match secp_format {
+            SecpFormat::Compressed => self.to_compressed_bytes().to_vec(),
+            SecpFormat::Uncompressed => self.to_bytes().to_vec(),
+        }
+    }
+}
+
+impl KeyFormat for Secp256r1PrivateKey {
+    const PEM_LABEL: &'static str = PRIVATE_KEY_PEM_LABEL;
+    const DER_PREFIX: Option<&'static [u8]> = None;
+    const LENGTHS: &'static [usize] = &[SECP256R1_PRIVATE_KEY_LENGTH];
+    const KEY_TYPE: Option<KeyType> = Some(KeyType::Secp256r1);
+    const PRIVATE: bool = true;
+}
+
+/// Either SEC1 form is decoded to the same key
+impl KeyFormat for Secp256r1PublicKey {
+    const PEM_LABEL: &'static str = PUBLIC_KEY_PEM_LABEL;
+    const DER_PREFIX: Option<&'static [u8]> = None;
+    const LENGTHS: &'static [usize] = &[
+        SECP256R1_COMPRESSED_PUBLIC_KEY_LENGTH,
+        SECP256R1_PUBLIC_KEY_LENGTH,
+    ];
+    const KEY_TYPE: Option<KeyType> = Some(KeyType::Secp256r1);
+    const PRIVATE: bool = false;
+
+    fn raw_bytes(&self, secp_format: SecpFormat) -> Vec<u8> {
+        match secp_format {
+            SecpFormat::Compressed => self.to_compressed_bytes().to_vec(),
+            SecpFormat::Uncompressed => self.to_bytes().to_vec(),
+        }
+    }
 }
 
 impl EncodingType {
+    /// All encodings, in the order they're listed in messages
+    pub const ALL: &'static [EncodingType] = &[
+        EncodingType::Hex,
+        EncodingType::BCS,
+        EncodingType::Base64,
+        EncodingType::Base64Url,
+        EncodingType::Pem,
+        EncodingType::Der,
+        EncodingType::Raw,
+        EncodingType::Bech32,
+        EncodingType::Auto,
+    ];
+
+    /// Name of the encoding, as used in messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            EncodingType::BCS => "bcs",
+            EncodingType::Hex => "hex",
+            EncodingType::Base64 => "base64",
+            EncodingType::Base64Url => "base64url",
+            EncodingType::Pem => "pem",
+            EncodingType::Der => "der",
+            EncodingType::Raw => "raw",
+            EncodingType::Bech32 => "bech32",
+            EncodingType::Auto => "auto",
+        }
+    }
+
     /// Encodes `Key` into one of the `EncodingType`s
-    pub fn encode_key<Key: ValidCryptoMaterial>(
+    ///
+    /// The encoded key and any copies of the raw key are zeroized when dropped, as they may be
+    /// private keys.
+    pub fn encode_key<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+        key: &Key,
+    ) -> CliTypedResult<Zeroizing<Vec<u8>>> {
+        self.encode_key_with(name, key, KeyEncodeOptions::default())
+    }
+
+    /// Encodes `Key` like `encode_key`, with hex, `bech32` and `secp256k1` public keys in the
+    /// forms of the `options`
+    pub fn encode_key_with<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+        key: &Key,
+        options: KeyEncodeOptions,
+    ) -> CliTypedResult<Zeroizing<Vec<u8>>> {
+        let bytes = Zeroizing::new(key.raw_bytes(options.secp_format));
+        Ok(Zeroizing::new(match self {
+            EncodingType::Auto => return Err(Self::auto_can_not_encode(name)),
+            EncodingType::Hex => match options.hex_case {
+                HexCase::Upper => hex::encode_upper(bytes.as_slice()),
+                HexCase::Lower => hex::encode(bytes.as_slice()),
+            }
+            .into_bytes(),
+            EncodingType::BCS => {
+                // Keys are BCS serialized as bytes, so serialize the copy that's zeroized, into
+                // a buffer big enough to never be reallocated, leaving no copies behind
+                let mut encoded = Vec::with_capacity(bytes.len() + MAX_ULEB128_LENGTH);
+                let capacity = encoded.capacity();
+                bcs::serialize_into(&mut encoded, bytes.as_slice())
+                    .map_err(|err| CliError::BCS(name, err))?;
+                debug_assert_eq!(
+                    encoded.capacity(),
+                    capacity,
+                    "BCS buffer was reallocated, leaving a copy of the key behind"
+                );
+                encoded
+            }
+            EncodingType::Base64 | EncodingType::Base64Url => {
+                base64::encode_config(bytes.as_slice(), self.base64_config(options.base64_no_pad))
+                    .into_bytes()
+            }
+            EncodingType::Pem => {
+                // Keys with a DER encoding are armored DER, as OpenSSL writes them, and other
+                // keys are armored raw bytes
+                let prefix = Key::DER_PREFIX.unwrap_or_default();
+                let mut contents = Vec::with_capacity(prefix.len() + bytes.len());
+                contents.extend_from_slice(prefix);
+                contents.extend_from_slice(&bytes);
+                let mut pem = pem::Pem {
+                    tag: Key::PEM_LABEL.to_string(),
+                    contents,
+                };
+                let encoded = pem::encode_config(
+                    &pem,
+                    pem::EncodeConfig {
+                        line_ending: pem::LineEnding::LF,
+                    },
+                );
+                pem.contents.zeroize();
+                encoded.into_bytes()
+            }
+            EncodingType::Der => {
+                let prefix = Self::der_prefix::<Key>(name)?;
+                let mut encoded = Vec::with_capacity(prefix.len() + bytes.len());
+                encoded.extend_from_slice(prefix);
+                encoded.extend_from_slice(&bytes);
+                encoded
+            }
+            EncodingType::Raw => bytes.to_vec(),
+            EncodingType::Bech32 => Self::encode_bech32(name, &options.bech32_hrp, &bytes)?,
+        }))
+    }
+
+    /// Writes `Key` encoded like `encode_key_with` to `writer`
+    ///
+    /// Hex and base 64 are streamed in chunks rather than encoded all at once, for large public
+    /// keys like multi-ed25519 keys.  The base 64 encoder's buffer isn't zeroized, so this is
+    /// only for public keys.  The other encodings are written whole.
+    pub fn write_key_with<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+        key: &Key,
+        options: KeyEncodeOptions,
+        writer: &mut dyn Write,
+    ) -> CliTypedResult<()> {
+        let to_cli_error = |err| CliError::IO(name.to_string(), err);
+        match self {
+            EncodingType::Hex => {
+                let bytes = Zeroizing::new(key.raw_bytes(options.secp_format));
+                for chunk in bytes.chunks(ENCODE_CHUNK_SIZE) {
+                    let encoded = Zeroizing::new(match options.hex_case {
+                        HexCase::Upper => hex::encode_upper(chunk),
+                        HexCase::Lower => hex::encode(chunk),
+                    });
+                    writer.write_all(encoded.as_bytes()).map_err(to_cli_error)?;
+                }
+                Ok(())
+            }
+            EncodingType::Base64 | EncodingType::Base64Url => {
+                let config = self.base64_config(options.base64_no_pad);
+                let bytes = Zeroizing::new(key.raw_bytes(options.secp_format));
+                let mut encoder = base64::write::EncoderWriter::new(writer, config);
+                encoder.write_all(&bytes).map_err(to_cli_error)?;
+                encoder.finish().map_err(to_cli_error)?;
+                Ok(())
+            }
+            _ => writer
+                .write_all(&self.encode_key_with(name, key, options)?)
+                .map_err(to_cli_error),
+        }
+    }
+
+    /// Alphabet and padding of keys written with the base 64 encodings
+    ///
+    /// `base64url` keys are never padded.  Reading either encoding accepts keys with or without
+    /// padding.
+    fn base64_config(&self, no_pad: bool) -> base64::Config {
+        match self {
+            EncodingType::Base64Url => base64::URL_SAFE_NO_PAD,
+            _ if no_pad => base64::STANDARD_NO_PAD,
+            _ => base64::STANDARD,
+        }
+    }
+
+    /// Saves `key` encoded with the encoding to `writer`, e.g. an in-memory buffer
+    ///
+    /// Private keys are encoded whole, so no unzeroized copy is left behind, and public keys
+    /// are streamed like [`EncodingType::write_key_with`].
+    pub fn save_key_to<Key: ValidCryptoMaterial + KeyFormat>(
         &self,
         name: &'static str,
         key: &Key,
+        mut writer: impl Write,
+    ) -> CliTypedResult<()> {
+        if Key::PRIVATE {
+            writer
+                .write_all(&self.encode_key(name, key)?)
+                .map_err(|err| CliError::IO(name.to_string(), err))
+        } else {
+            self.write_key_with(name, key, KeyEncodeOptions::default(), &mut writer)
+        }
+    }
+
+    /// Encodes bytes that aren't a key, labelled with `pem_label` in PEM armor
+    ///
+    /// BCS encodes the bytes as a byte vector, so `decode_bytes` reverses this.  DER can only
+    /// encode keys.
+    pub fn encode_bytes(
+        &self,
+        name: &'static str,
+        pem_label: &str,
+        bytes: &[u8],
     ) -> CliTypedResult<Vec<u8>> {
-        Ok(match self {
-            EncodingType::Hex => hex::encode_upper(key.to_bytes()).into_bytes(),
-            EncodingType::BCS => bcs::to_bytes(key).map_err(|err| CliError::BCS(name, err))?,
-            EncodingType::Base64 => base64::encode(key.to_bytes()).into_bytes(),
-        })
+        match self {
+            EncodingType::Auto => Err(Self::auto_can_not_encode(name)),
+            EncodingType::Hex => Ok(hex::encode_upper(bytes).into_bytes()),
+            EncodingType::BCS => bcs::to_bytes(bytes).map_err(|err| CliError::BCS(name, err)),
+            EncodingType::Base64 => Ok(base64::encode(bytes).into_bytes()),
+            EncodingType::Base64Url => {
+                Ok(base64::encode_config(bytes, base64::URL_SAFE_NO_PAD).into_bytes())
+            }
+            EncodingType::Pem => Ok(pem::encode_config(
+                &pem::Pem {
+                    tag: pem_label.to_string(),
+                    contents: bytes.to_vec(),
+                },
+                pem::EncodeConfig {
+                    line_ending: pem::LineEnding::LF,
+                },
+            )
+            .into_bytes()),
+            EncodingType::Der => Err(CliError::CommandArgumentError(format!(
+                "{} can't be DER encoded, only keys can",
+                name
+            ))),
+            EncodingType::Raw => Ok(bytes.to_vec()),
+            EncodingType::Bech32 => Self::encode_bech32(name, DEFAULT_BECH32_HRP, bytes),
+        }
     }
 
-    /// Loads a key from a file
-    pub fn load_key<Key: ValidCryptoMaterial>(
+    /// Loads a key from a file, or from stdin if the path is `-`
+    ///
+    /// Keys encrypted with a passphrase are decrypted after prompting for the passphrase.  A
+    /// file with a checksum file is checked against it.
+    pub fn load_key<Key: ValidCryptoMaterial + KeyFormat>(
         &self,
         name: &'static str,
         path: &Path,
     ) -> CliTypedResult<Key> {
-        self.decode_key(name, read_from_file(path)?)
+        self.load_key_with(name, path, &PassphraseOptions::default())
+    }
+
+    /// Loads a key like [`EncodingType::load_key`], decrypting an encrypted key with the
+    /// passphrase from `passphrase_options`
+    pub fn load_key_with<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+        path: &Path,
+        passphrase_options: &PassphraseOptions,
+    ) -> CliTypedResult<Key> {
+        KeyFile::new(path, *self)
+            .with_passphrase_options(passphrase_options.clone())
+            .load(name)
+    }
+
+    /// Loads a key from `reader`, e.g. an in-memory buffer, like [`EncodingType::load_key`]
+    ///
+    /// There's no passphrase to decrypt an encrypted key with, or checksum file to check.
+    pub fn load_key_from<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+        reader: impl Read,
+    ) -> CliTypedResult<Key> {
+        let data = read_bounded(reader, name, MAX_KEY_SIZE)?;
+        if encryption::is_encrypted(&data) {
+            return Err(CliError::CommandArgumentError(format!(
+                "Encrypted key '{}' can't be read without a passphrase",
+                name
+            )));
+        }
+        self.decode_key(name, data)
+    }
+
+    /// Loads a key like [`EncodingType::load_key_with`] from a key source of at most `max_size`
+    /// bytes
+    ///
+    /// Only a key file can have a checksum file to check.
+    pub fn load_key_from_source<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+        source: &KeySource,
+        max_size: u64,
+        verify_checksum: bool,
+        passphrase_options: &PassphraseOptions,
+        bech32_hrp: &str,
+    ) -> CliTypedResult<Key> {
+        debug!(
+            "Loading {} ({}) from {} as {}",
+            name,
+            describe_key::<Key>(),
+            source,
+            self
+        );
+        let data = source.read(max_size)?;
+        if let (true, KeySource::File(path)) = (verify_checksum, source) {
+            utils::verify_checksum(path, &data)?;
+        }
+        if !encryption::is_encrypted(&data) {
+            return self.decode_key_with_hrp(name, data, bech32_hrp);
+        }
+
+        // The passphrase is read from stdin, so it can't also hold the key
+        if *source == KeySource::Stdin {
+            return Err(CliError::CommandArgumentError(format!(
+                "Encrypted key '{}' can't be read from stdin",
+                name
+            )));
+        }
+        self.decrypt_key(
+            name,
+            data,
+            &source.to_string(),
+            passphrase_options,
+            bech32_hrp,
+        )
+    }
+
+    /// Loads a key from a key store, decrypting it like [`EncodingType::load_key`] with the
+    /// passphrase from `passphrase_options`
+    ///
+    /// A `bech32` key must have the human readable part `bech32_hrp`.
+    pub fn load_key_from_store<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+        key_store: &dyn KeyStore,
+        passphrase_options: &PassphraseOptions,
+        bech32_hrp: &str,
+    ) -> CliTypedResult<Key> {
+        debug!(
+            "Loading {} ({}) from {} as {}",
+            name,
+            describe_key::<Key>(),
+            key_store.location(),
+            self
+        );
+        let data = key_store.load()?;
+        if !encryption::is_encrypted(&data) {
+            return self.decode_key_with_hrp(name, data, bech32_hrp);
+        }
+        self.decrypt_key(
+            name,
+            data,
+            &key_store.location(),
+            passphrase_options,
+            bech32_hrp,
+        )
+    }
+
+    /// Decrypts and decodes an encrypted key loaded from `location`
+    fn decrypt_key<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+        data: Vec<u8>,
+        location: &str,
+        passphrase_options: &PassphraseOptions,
+        bech32_hrp: &str,
+    ) -> CliTypedResult<Key> {
+        let data = Zeroizing::new(data);
+        let passphrase =
+            passphrase_options.passphrase(&format!("Enter passphrase for {}", location))?;
+        self.decode_key_with_hrp(
+            name,
+            encryption::decrypt(name, &data, &passphrase)?,
+            bech32_hrp,
+        )
     }
 
     /// Decodes an encoded key given the known encoding
-    pub fn decode_key<Key: ValidCryptoMaterial>(
+    ///
+    /// `data` and the decoded bytes are zeroized once the key is parsed.
+    pub fn decode_key<Key: ValidCryptoMaterial + KeyFormat>(
         &self,
         name: &'static str,
         data: Vec<u8>,
     ) -> CliTypedResult<Key> {
+        self.decode_key_with_hrp(name, data, DEFAULT_BECH32_HRP)
+    }
+
+    /// Decodes an encoded key like `decode_key`, where a `bech32` key must have the human
+    /// readable part `bech32_hrp`
+    pub fn decode_key_with_hrp<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+        data: Vec<u8>,
+        bech32_hrp: &str,
+    ) -> CliTypedResult<Key> {
+        let data = Zeroizing::new(data);
+        let bytes = Zeroizing::new(match self {
+            EncodingType::Auto => {
+                return Self::decode_auto(name, &data, |encoding, data| {
+                    encoding.decode_key_with_hrp(name, data, bech32_hrp)
+                })
+            }
+            EncodingType::BCS => {
+                bcs::from_bytes::<Vec<u8>>(&data).map_err(|err| CliError::BCS(name, err))?
+            }
+            EncodingType::Hex => Self::decode_hex(name, self.decode_text(name, &data)?)?,
+            EncodingType::Base64 => Self::check_key_length::<Key>(
+                name,
+                Self::decode_base64(name, self.decode_text(name, &data)?, base64::STANDARD)?,
+            )?,
+            EncodingType::Base64Url => Self::check_key_length::<Key>(
+                name,
+                Self::decode_base64(
+                    name,
+                    self.decode_text(name, &data)?,
+                    base64::URL_SAFE_NO_PAD,
+                )?,
+            )?,
+            EncodingType::Pem => {
+                let mut pem = Self::decode_pem(name, &data)?;
+                if pem.tag != Key::PEM_LABEL {
+                    return Err(CliError::UnableToParse(
+                        name,
+                        format!(
+                            "expected a PEM label of '{}', found '{}'",
+                            Key::PEM_LABEL,
+                            pem.tag
+                        ),
+                    ));
+                }
+                // PEM keys used to be armored raw bytes, which are still accepted
+                match Key::DER_PREFIX
+                    .and_then(|prefix| Self::strip_der_prefix(prefix, &pem.contents))
+                {
+                    Some(bytes) => {
+                        pem.contents.zeroize();
+                        bytes
+                    }
+                    None => pem.contents,
+                }
+            }
+            EncodingType::Der => {
+                let prefix = Self::der_prefix::<Key>(name)?;
+                if data.len() != prefix.len() + DER_KEY_LENGTH || !data.starts_with(prefix) {
+                    return Err(CliError::UnableToParse(
+                        name,
+                        format!(
+                            "expected {} bytes of DER starting with {}, found {} bytes",
+                            prefix.len() + DER_KEY_LENGTH,
+                            hex::encode(prefix),
+                            data.len()
+                        ),
+                    ));
+                }
+                data[prefix.len()..].to_vec()
+            }
+            // Unlike BCS there's no length prefix, so the length of the file is the length of
+            // the key
+            EncodingType::Raw => Self::check_key_length::<Key>(name, data.to_vec())?,
+            EncodingType::Bech32 => Self::check_key_length::<Key>(
+                name,
+                Self::decode_bech32(name, self.decode_text(name, &data)?, bech32_hrp)?,
+            )?,
+        });
+        Key::try_from(bytes.as_slice())
+            .map_err(|err| CliError::UnableToParse(name, format!("Failed to parse key {:?}", err)))
+    }
+
+    /// Checks decoded bytes have one of the lengths of a `Key`, so decoding the wrong file says
+    /// so rather than failing to parse
+    ///
+    /// The bytes are zeroized if they're rejected.
+    fn check_key_length<Key: KeyFormat>(
+        name: &'static str,
+        mut bytes: Vec<u8>,
+    ) -> CliTypedResult<Vec<u8>> {
+        if Key::LENGTHS.is_empty() || Key::LENGTHS.contains(&bytes.len()) {
+            return Ok(bytes);
+        }
+        let length = bytes.len();
+        bytes.zeroize();
+        let expected = Key::LENGTHS
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>()
+            .join(" or ");
+        Err(CliError::UnableToParse(
+            name,
+            format!("expected {} bytes, got {}", expected, length),
+        ))
+    }
+
+    /// Decodes encoded bytes given the known encoding, without interpreting them as a key
+    pub fn decode_bytes(&self, name: &'static str, data: Vec<u8>) -> CliTypedResult<Vec<u8>> {
+        self.decode_bytes_with_hrp(name, data, DEFAULT_BECH32_HRP)
+    }
+
+    /// Decodes encoded bytes like `decode_bytes`, where `bech32` bytes must have the human
+    /// readable part `bech32_hrp`
+    pub fn decode_bytes_with_hrp(
+        &self,
+        name: &'static str,
+        data: Vec<u8>,
+        bech32_hrp: &str,
+    ) -> CliTypedResult<Vec<u8>> {
         match self {
+            EncodingType::Auto => Self::decode_auto(name, &data, |encoding, data| {
+                encoding.decode_bytes_with_hrp(name, data, bech32_hrp)
+            }),
             EncodingType::BCS => bcs::from_bytes(&data).map_err(|err| CliError::BCS(name, err)),
-            EncodingType::Hex => {
-                let hex_string = String::from_utf8(data).unwrap();
-                Key::from_encoded_string(hex_string.trim())
-                    .map_err(|err| CliError::UnableToParse(name, err.to_string()))
-            }
+            EncodingType::Hex => Self::decode_hex(name, self.decode_text(name, &data)?),
             EncodingType::Base64 => {
-                let string = String::from_utf8(data).unwrap();
-                let bytes = base64::decode(string.trim())
-                    .map_err(|err| CliError::UnableToParse(name, err.to_string()))?;
-                Key::try_from(bytes.as_slice()).map_err(|err| {
-                    CliError::UnableToParse(name, format!("Failed to parse key {:?}", err))
-                })
+                Self::decode_base64(name, self.decode_text(name, &data)?, base64::STANDARD)
+            }
+            EncodingType::Base64Url => Self::decode_base64(
+                name,
+                self.decode_text(name, &data)?,
+                base64::URL_SAFE_NO_PAD,
+            ),
+            EncodingType::Pem => {
+                let contents = Self::decode_pem(name, &data)?.contents;
+                Ok(DER_PREFIXES
+                    .iter()
+                    .find_map(|prefix| Self::strip_der_prefix(prefix, &contents))
+                    .unwrap_or(contents))
+            }
+            EncodingType::Der => DER_PREFIXES
+                .iter()
+                .find_map(|prefix| Self::strip_der_prefix(prefix, &data))
+                .ok_or_else(|| {
+                    CliError::UnableToParse(
+                        name,
+                        "not a DER encoded ed25519 or x25519 key".to_string(),
+                    )
+                }),
+            EncodingType::Raw => Ok(data),
+            EncodingType::Bech32 => {
+                Self::decode_bech32(name, self.decode_text(name, &data)?, bech32_hrp)
             }
         }
     }
+
+    /// Decodes with each of the `AUTO_ENCODINGS` in turn, returning the first that works, or an
+    /// error saying why each failed
+    fn decode_auto<T>(
+        name: &'static str,
+        data: &[u8],
+        decode: impl Fn(EncodingType, Vec<u8>) -> CliTypedResult<T>,
+    ) -> CliTypedResult<T> {
+        let mut failures = Vec::new();
+        for encoding in AUTO_ENCODINGS {
+            match decode(*encoding, data.to_vec()) {
+                Ok(decoded) => {
+                    debug!("Detected {} as {}", name, encoding);
+                    return Ok(decoded);
+                }
+                Err(err) => failures.push(format!("{}: {}", encoding, err)),
+            }
+        }
+        Err(CliError::UnableToParse(
+            name,
+            format!(
+                "couldn't detect the encoding, tried {}",
+                failures.join(", ")
+            ),
+        ))
+    }
+
+    fn auto_can_not_encode(name: &'static str) -> CliError {
+        CliError::CommandArgumentError(format!(
+            "{} can't be written with the auto encoding, it's only for reading",
+            name
+        ))
+    }
+
+    /// Reads text encoded data, failing clearly if it's binary e.g. a BCS file given the wrong
+    /// encoding
+    fn decode_text<'a>(&self, name: &'static str, data: &'a [u8]) -> CliTypedResult<&'a str> {
+        std::str::from_utf8(data).map_err(|_| {
+            CliError::UnableToParse(
+                name,
+                format!(
+                    "it isn't text, so it doesn't look {} encoded, is the encoding wrong?",
+                    self
+                ),
+            )
+        })
+    }
+
+    /// Decodes hex, ignoring whitespace anywhere e.g. from wrapped lines, and a `0x` prefix
+    fn decode_hex(name: &'static str, text: &str) -> CliTypedResult<Vec<u8>> {
+        let stripped = strip_whitespace(text);
+        let hex_string = stripped.as_str();
+        hex::decode(hex_string.strip_prefix("0x").unwrap_or(hex_string))
+            .map_err(|err| CliError::UnableToParse(name, err.to_string()))
+    }
+
+    /// Decodes base 64, ignoring whitespace anywhere e.g. from lines wrapped at 64 columns
+    fn decode_base64(
+        name: &'static str,
+        text: &str,
+        config: base64::Config,
+    ) -> CliTypedResult<Vec<u8>> {
+        base64::decode_config(strip_whitespace(text).as_bytes(), config)
+            .map_err(|err| CliError::UnableToParse(name, err.to_string()))
+    }
+
+    /// Encodes bytes as `bech32` with the human readable part `hrp`
+    ///
+    /// The bech32 crate copies the bytes into base 32 digits that can't be zeroized, so a
+    /// `bech32` private key may leave a copy behind in freed memory.
+    fn encode_bech32(name: &'static str, hrp: &str, bytes: &[u8]) -> CliTypedResult<Vec<u8>> {
+        bech32::encode(hrp, bytes.to_base32(), bech32::Variant::Bech32)
+            .map(String::into_bytes)
+            .map_err(|err| {
+                CliError::CommandArgumentError(format!(
+                    "{} can't be bech32 encoded with the prefix '{}': {}",
+                    name, hrp, err
+                ))
+            })
+    }
+
+    /// Decodes `bech32`, checking its checksum and that its human readable part is `hrp`
+    ///
+    /// Whitespace around the text, e.g. a trailing newline, is ignored.
+    fn decode_bech32(name: &'static str, text: &str, hrp: &str) -> CliTypedResult<Vec<u8>> {
+        let (found_hrp, data, variant) = bech32::decode(text.trim())
+            .map_err(|err| CliError::UnableToParse(name, err.to_string()))?;
+        if variant != bech32::Variant::Bech32 {
+            return Err(CliError::UnableToParse(
+                name,
+                "expected bech32, found bech32m".to_string(),
+            ));
+        }
+        if found_hrp != hrp {
+            return Err(CliError::UnableToParse(
+                name,
+                format!("expected the prefix '{}', found '{}'", hrp, found_hrp),
+            ));
+        }
+        Vec::<u8>::from_base32(&data).map_err(|err| CliError::UnableToParse(name, err.to_string()))
+    }
+
+    /// DER prefix of `Key`, failing if it has no DER encoding
+    fn der_prefix<Key: KeyFormat>(name: &'static str) -> CliTypedResult<&'static [u8]> {
+        Key::DER_PREFIX.ok_or_else(|| {
+            CliError::UnableToParse(
+                name,
+                "DER encoding is only supported for ed25519 and x25519 keys".to_string(),
+            )
+        })
+    }
+
+    /// Raw key bytes of DER encoded `data`, if it's a key with the DER prefix `prefix`
+    fn strip_der_prefix(prefix: &[u8], data: &[u8]) -> Option<Vec<u8>> {
+        if data.len() == prefix.len() + DER_KEY_LENGTH && data.starts_with(prefix) {
+            Some(data[prefix.len()..].to_vec())
+        } else {
+            None
+        }
+    }
+
+    /// Decodes the only PEM object in `data`, rejecting files with more than one
+    fn decode_pem(name: &'static str, data: &[u8]) -> CliTypedResult<pem::Pem> {
+        let mut objects = pem::parse_many(data);
+        match objects.len() {
+            1 => Ok(objects.remove(0)),
+            0 => Err(CliError::UnableToParse(
+                name,
+                "no PEM encoded object found".to_string(),
+            )),
+            count => Err(CliError::UnableToParse(
+                name,
+                format!("found {} PEM encoded objects, expected only one", count),
+            )),
+        }
+    }
+}
+
+/// Removes all whitespace from text that may hold a key, zeroizing the copy once it's dropped
+fn strip_whitespace(text: &str) -> Zeroizing<String> {
+    Zeroizing::new(text.chars().filter(|c| !c.is_whitespace()).collect())
+}
+
+impl std::fmt::Display for EncodingType {
+    /// Shows the encoding by name, which parses back to the same encoding
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 impl FromStr for EncodingType {
-    type Err = &'static str;
+    type Err = String;
+
+    /// Parses an encoding by name, in any case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_by_name(s, "encoding type", EncodingType::ALL, EncodingType::name)
+    }
+}
+
+/// Cases of hex digits, when encoding
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexCase {
+    Upper,
+    Lower,
+}
+
+impl HexCase {
+    /// All cases, in the order they're listed in messages
+    pub const ALL: &'static [HexCase] = &[HexCase::Upper, HexCase::Lower];
+
+    /// Name of the case, as used in messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            HexCase::Upper => "upper",
+            HexCase::Lower => "lower",
+        }
+    }
+}
+
+impl FromStr for HexCase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_by_name(s, "hex case", HexCase::ALL, HexCase::name)
+    }
+}
+
+/// SEC1 forms of `secp256k1` and `secp256r1` public keys, when encoding
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecpFormat {
+    /// 33 bytes, the x coordinate and the sign of y
+    Compressed,
+    /// 65 bytes, both coordinates
+    Uncompressed,
+}
+
+impl SecpFormat {
+    /// All forms, in the order they're listed in messages
+    pub const ALL: &'static [SecpFormat] = &[SecpFormat::Compressed, SecpFormat::Uncompressed];
+
+    /// Name of the form, as used in messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            SecpFormat::Compressed => "compressed",
+            SecpFormat::Uncompressed => "uncompressed",
+        }
+    }
+}
+
+impl FromStr for SecpFormat {
+    type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "hex" => Ok(EncodingType::Hex),
-            "bcs" => Ok(EncodingType::BCS),
-            "base64" => Ok(EncodingType::Base64),
-            _ => Err("Invalid encoding type"),
+        parse_by_name(s, "secp format", SecpFormat::ALL, SecpFormat::name)
+    }
+}
+
+/// Forms of encoded keys
+///
+/// Only the human readable part of `bech32` keys has to be the same to decode them again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyEncodeOptions {
+    pub hex_case: HexCase,
+    pub secp_format: SecpFormat,
+    pub bech32_hrp: String,
+    pub base64_no_pad: bool,
+}
+
+impl Default for KeyEncodeOptions {
+    /// Uppercase hex, compressed `secp256k1` public keys, the default `bech32` human readable
+    /// part, and padded `base64`
+    fn default() -> Self {
+        KeyEncodeOptions {
+            hex_case: HexCase::Upper,
+            secp_format: SecpFormat::Compressed,
+            bech32_hrp: DEFAULT_BECH32_HRP.to_string(),
+            base64_no_pad: false,
         }
     }
 }
@@ -348,12 +1451,20 @@ impl FromStr for EncodingType {
 /// An insertable option for use with prompts.
 #[derive(Clone, Copy, Debug, Parser)]
 pub struct PromptOptions {
-    /// Assume yes for all yes/no prompts
-    #[clap(long, group = "prompt_options")]
+    /// Assume yes for all yes/no prompts, e.g. to overwrite files
+    #[clap(long, group = "prompt_options", visible_alias = "force")]
     pub assume_yes: bool,
     /// Assume no for all yes/no prompts
     #[clap(long, group = "prompt_options")]
     pub assume_no: bool,
+    /// Never overwrite an existing file, failing at once without prompting instead
+    ///
+    /// Takes precedence over `--assume-yes`, so scripts can be rerun safely.
+    #[clap(long)]
+    pub no_overwrite: bool,
+    /// Seconds to wait for an answer to a yes/no prompt, aborting as if `no` was answered
+    #[clap(long)]
+    pub prompt_timeout: Option<u64>,
 }
 
 impl PromptOptions {
@@ -361,21 +1472,490 @@ impl PromptOptions {
         Self {
             assume_yes: true,
             assume_no: false,
+            no_overwrite: false,
+            prompt_timeout: None,
+        }
+    }
+
+    /// Whether existing files are overwritten without prompting, with `--assume-yes` and
+    /// without `--no-overwrite`
+    pub fn overwrites(&self) -> bool {
+        self.assume_yes && !self.no_overwrite
+    }
+
+    /// Fails for the existing `location` with `--no-overwrite`, before any prompt
+    pub fn check_no_overwrite(&self, location: &str) -> CliTypedResult<()> {
+        if self.no_overwrite {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} already exists, and --no-overwrite never overwrites it",
+                location
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// An insertable option for where the passphrase of an encrypted key comes from
+///
+/// The passphrase is read from `--passphrase-env`, then from `--passphrase-file`, and is
+/// otherwise prompted for.  There's no option for the passphrase itself, as command lines can be
+/// seen by other users.
+#[derive(Clone, Debug, Default, Parser, PartialEq, Eq)]
+pub struct PassphraseOptions {
+    /// Environment variable to read the passphrase from, instead of prompting for it
+    #[clap(long)]
+    pub passphrase_env: Option<String>,
+    /// File to read the passphrase from, instead of prompting for it
+    ///
+    /// Whitespace around the passphrase, e.g. a trailing newline, is ignored.
+    #[clap(long, parse(from_os_str))]
+    pub passphrase_file: Option<PathBuf>,
+}
+
+impl PassphraseOptions {
+    /// The passphrase of an encrypted key, prompted for with `prompt` if it isn't given
+    pub fn passphrase(&self, prompt: &str) -> CliTypedResult<Zeroizing<String>> {
+        match self.given_passphrase()? {
+            Some(passphrase) => Ok(passphrase),
+            None => read_passphrase(prompt),
+        }
+    }
+
+    /// A new passphrase to encrypt a key with, prompted for twice if it isn't given
+    pub fn new_passphrase(&self) -> CliTypedResult<Zeroizing<String>> {
+        match self.given_passphrase()? {
+            Some(passphrase) if passphrase.is_empty() => Err(CliError::CommandArgumentError(
+                "Passphrase must not be empty".to_string(),
+            )),
+            Some(passphrase) => Ok(passphrase),
+            None => read_new_passphrase(),
+        }
+    }
+
+    /// The passphrase from the environment or a file, if either is given
+    fn given_passphrase(&self) -> CliTypedResult<Option<Zeroizing<String>>> {
+        if let Some(ref variable) = self.passphrase_env {
+            let passphrase = std::env::var(variable).map_err(|err| {
+                CliError::CommandArgumentError(format!(
+                    "Unable to read the passphrase from ${}: {}",
+                    variable, err
+                ))
+            })?;
+            return Ok(Some(Zeroizing::new(passphrase)));
         }
+        if let Some(ref file) = self.passphrase_file {
+            let contents = Zeroizing::new(read_from_file(file)?);
+            let passphrase = std::str::from_utf8(&contents).map_err(|_| {
+                CliError::UnableToParse("--passphrase-file", "it isn't text".to_string())
+            })?;
+            return Ok(Some(Zeroizing::new(passphrase.trim().to_string())));
+        }
+        Ok(None)
     }
 }
 
 /// An insertable option for use with encodings.
-#[derive(Debug, Parser)]
+#[derive(Clone, Debug, Parser)]
 pub struct EncodingOptions {
-    /// Encoding of data as `base64`, `bcs`, or `hex`
+    /// Encoding of data as `base64`, `base64url`, `bcs`, `bech32`, `der`, `hex`, `pem`, or `raw`
+    ///
+    /// Keys that are read can also be `auto`, which detects `hex`, `base64`, or `bcs`.
     #[clap(long, default_value = "hex")]
     pub encoding: EncodingType,
+    /// Human readable part of `bech32` keys, which a key that's read must have
+    #[clap(long, default_value = DEFAULT_BECH32_HRP)]
+    pub bech32_hrp: String,
+}
+
+impl EncodingOptions {
+    /// A key file in the encoding, whose `bech32` keys have the human readable part
+    pub fn key_file(&self, path: impl Into<PathBuf>) -> KeyFile {
+        KeyFile::new(path, self.encoding).with_bech32_hrp(self.bech32_hrp.clone())
+    }
+}
+
+/// An insertable option for the input and output files of a command
+///
+/// Either can be `-`, which is the default, for stdin or stdout.  A private key is only written
+/// to stdout with `--print-private`.
+#[derive(Clone, Debug, Parser)]
+pub struct IoOptions {
+    /// Input file name, or `-` to read from stdin
+    #[clap(
+        long,
+        aliases = &["input-file", "key-file", "private-key-file"],
+        parse(from_os_str),
+        default_value = STDIN_PATH
+    )]
+    pub input: PathBuf,
+    /// Output file name, or `-` to write to stdout
+    #[clap(long, alias = "output-file", parse(from_os_str))]
+    pub output: Option<PathBuf>,
+    /// Allow writing a private key to stdout, where it may end up in logs or shell history
+    #[clap(long)]
+    pub print_private: bool,
+}
+
+impl IoOptions {
+    /// Reads all of the input
+    pub fn read_input(&self) -> CliTypedResult<Vec<u8>> {
+        read_from_file_or_stdin(&self.input)
+    }
+
+    /// Whether the output is written to stdout, as it is unless `--output` is a file
+    pub fn is_stdout(&self) -> bool {
+        self.output.as_deref().map_or(true, is_stdout_path)
+    }
+
+    /// Checks the output can be written, prompting if an output file already exists
+    ///
+    /// A `confidential` output, i.e. a private key, is refused for stdout without
+    /// `--print-private`.
+    pub fn check_output(
+        &self,
+        prompt_options: PromptOptions,
+        confidential: bool,
+    ) -> CliTypedResult<()> {
+        match self.output {
+            Some(ref output) if !is_stdout_path(output) => {
+                check_if_file_exists(output, prompt_options)
+            }
+            _ if confidential && !self.print_private => Err(CliError::CommandArgumentError(
+                "A private key is only written to stdout with --print-private".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes the output, to a file readable only by the user if it's `confidential` and by
+    /// anyone otherwise
+    ///
+    /// Returns the output file, or `None` for stdout.
+    pub fn write_output(
+        &self,
+        name: &str,
+        bytes: &[u8],
+        confidential: bool,
+    ) -> CliTypedResult<Option<PathBuf>> {
+        let output = match self.output {
+            Some(ref output) if !is_stdout_path(output) => output,
+            _ => {
+                write_to_file_or_stdout(Path::new(STDOUT_PATH), name, bytes)?;
+                return Ok(None);
+            }
+        };
+        if confidential {
+            write_to_user_only_file(output, name, bytes)?;
+        } else {
+            write_to_public_file(output, name, bytes)?;
+        }
+        Ok(Some(output.clone()))
+    }
+}
+
+/// An insertable option for skipping the checksum check of key files
+#[derive(Clone, Debug, Default, Parser)]
+pub struct ChecksumOptions {
+    /// Don't check the key file against its `.sha256` checksum file
+    ///
+    /// Key files with a checksum file, as saved with `--with-checksum`, are otherwise checked
+    /// when they're loaded.
+    #[clap(long)]
+    pub skip_checksum: bool,
+}
+
+impl ChecksumOptions {
+    /// Loads a key like [`EncodingType::load_key`], unless skipping its checksum check
+    pub fn load_key<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        encoding: EncodingType,
+        name: &'static str,
+        path: &Path,
+    ) -> CliTypedResult<Key> {
+        self.load(&KeyFile::new(path, encoding), name)
+    }
+
+    /// Loads a key from a `KeyFile`, unless skipping its checksum check
+    pub fn load<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        key_file: &KeyFile,
+        name: &'static str,
+    ) -> CliTypedResult<Key> {
+        key_file.load_with(name, !self.skip_checksum)
+    }
+}
+
+/// A key file, with the encoding of the key in it and optionally the type of the key
+///
+/// The path is kept with its encoding, so a key can't be loaded or saved with the encoding of
+/// another file.  With a key type, only keys of that type are loaded or saved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyFile {
+    /// Path of the key file, or `-` for stdin
+    pub path: PathBuf,
+    pub encoding: EncodingType,
+    pub key_type: Option<KeyType>,
+    /// Where the passphrase of an encrypted key comes from
+    pub passphrase_options: PassphraseOptions,
+    /// Forms of saved keys, whose human readable part of `bech32` keys is also the one of
+    /// loaded keys
+    pub encode_options: KeyEncodeOptions,
+    /// Whether a newline is appended to saved keys of the text encodings
+    pub trailing_newline: bool,
+}
+
+impl KeyFile {
+    pub fn new(path: impl Into<PathBuf>, encoding: EncodingType) -> Self {
+        KeyFile {
+            path: path.into(),
+            encoding,
+            key_type: None,
+            passphrase_options: PassphraseOptions::default(),
+            encode_options: KeyEncodeOptions::default(),
+            trailing_newline: false,
+        }
+    }
+
+    /// Restricts the key file to keys of `key_type`
+    pub fn with_key_type(mut self, key_type: KeyType) -> Self {
+        self.key_type = Some(key_type);
+        self
+    }
+
+    /// Decrypts an encrypted key with the passphrase from `passphrase_options`, instead of
+    /// prompting for it
+    pub fn with_passphrase_options(mut self, passphrase_options: PassphraseOptions) -> Self {
+        self.passphrase_options = passphrase_options;
+        self
+    }
+
+    /// Reads and writes `bech32` keys with the human readable part `bech32_hrp`
+    pub fn with_bech32_hrp(mut self, bech32_hrp: impl Into<String>) -> Self {
+        self.encode_options.bech32_hrp = bech32_hrp.into();
+        self
+    }
+
+    /// Saves keys in the forms of `encode_options`, including its `bech32` human readable part
+    pub fn with_encode_options(mut self, encode_options: KeyEncodeOptions) -> Self {
+        self.encode_options = encode_options;
+        self
+    }
+
+    /// Appends a newline to saved keys of the text encodings
+    pub fn with_trailing_newline(mut self, trailing_newline: bool) -> Self {
+        self.trailing_newline = trailing_newline;
+        self
+    }
+
+    /// Loads the key like [`EncodingType::load_key`]
+    pub fn load<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+    ) -> CliTypedResult<Key> {
+        self.load_with(name, true)
+    }
+
+    /// Loads the key like [`KeyFile::load`], only checking its checksum file with
+    /// `verify_checksum`
+    pub fn load_with<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+        verify_checksum: bool,
+    ) -> CliTypedResult<Key> {
+        self.check_key_type::<Key>(name)?;
+        self.tagged_encoding::<Key>(name)?.load_key_from_source(
+            name,
+            &KeySource::from_path(&self.path),
+            MAX_KEY_SIZE,
+            verify_checksum,
+            &self.passphrase_options,
+            &self.encode_options.bech32_hrp,
+        )
+    }
+
+    /// Saves `key` encoded with the encoding, readable only by the User if it's a private key
+    pub fn save<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+        key: &Key,
+    ) -> CliTypedResult<()> {
+        self.check_key_type::<Key>(name)?;
+        debug!(
+            "Saving {} ({}) to {} as {}",
+            name,
+            describe_key::<Key>(),
+            self.path.display(),
+            self.encoding
+        );
+        let encoded = self.encode(name, key)?;
+        if Key::PRIVATE {
+            write_to_user_only_file(&self.path, name, &encoded)
+        } else {
+            write_to_public_file(&self.path, name, &encoded)
+        }
+    }
+
+    /// Encodes `key` as it's saved, in the forms of the encode options and with any trailing
+    /// newline
+    pub fn encode<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        name: &'static str,
+        key: &Key,
+    ) -> CliTypedResult<Zeroizing<Vec<u8>>> {
+        let encoded = self
+            .encoding
+            .encode_key_with(name, key, self.encode_options.clone())?;
+        if !self.appends_newline() {
+            return Ok(encoded);
+        }
+        // Copied rather than pushed, so a reallocation can't leave an unzeroized copy behind
+        let mut with_newline = Zeroizing::new(Vec::with_capacity(encoded.len() + 1));
+        with_newline.extend_from_slice(&encoded);
+        with_newline.push(b'\n');
+        Ok(with_newline)
+    }
+
+    /// Whether a newline is appended to saved keys, with `trailing_newline` for text encodings
+    fn appends_newline(&self) -> bool {
+        self.trailing_newline
+            && matches!(
+                self.encoding,
+                EncodingType::Hex
+                    | EncodingType::Base64
+                    | EncodingType::Base64Url
+                    | EncodingType::Bech32
+            )
+    }
+
+    /// Checks a `Key` is of the key type, if the key file has one
+    fn check_key_type<Key: KeyFormat>(&self, name: &'static str) -> CliTypedResult<()> {
+        match self.key_type {
+            Some(key_type) if Key::KEY_TYPE != Some(key_type) => {
+                Err(CliError::CommandArgumentError(format!(
+                    "{} '{}' is a {} key file, and can't hold another type of key",
+                    name,
+                    self.path.display(),
+                    key_type
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The encoding of the key file's algorithm tag, when the encoding is `auto`
+    ///
+    /// A tag of another key type than `Key` fails, rather than reading the key as the wrong type.
+    fn tagged_encoding<Key: KeyFormat>(&self, name: &'static str) -> CliTypedResult<EncodingType> {
+        if self.encoding != EncodingType::Auto || self.path == Path::new(STDIN_PATH) {
+            return Ok(self.encoding);
+        }
+        match AlgorithmTag::load(&self.path)? {
+            Some(tag) if Key::KEY_TYPE.is_some() && Key::KEY_TYPE != Some(tag.key_type) => {
+                Err(CliError::CommandArgumentError(format!(
+                    "{} '{}' is tagged as a {} key",
+                    name,
+                    self.path.display(),
+                    tag.key_type
+                )))
+            }
+            Some(tag) => Ok(tag.encoding),
+            None => Ok(self.encoding),
+        }
+    }
+}
+
+/// Extension of the file beside a key file tagging its algorithm, e.g. `key.alg`
+pub const ALGORITHM_TAG_EXTENSION: &str = "alg";
+
+/// First word of every algorithm tag, changed whenever the tag's fields change meaning
+pub const ALGORITHM_TAG_VERSION: &str = "aptos-key-alg/1";
+
+/// The algorithm of a key file and its encoding, as saved beside it with `--algorithm-agility`
+///
+/// The tag is a single line, the version followed by `name=value` fields, e.g.
+/// `aptos-key-alg/1 key_type=ed25519 curve=edwards25519 scheme=eddsa encoding=hex`.  The curve
+/// and scheme follow from the key type, and are there so consumers never have to guess them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AlgorithmTag {
+    pub key_type: KeyType,
+    pub encoding: EncodingType,
+}
+
+impl AlgorithmTag {
+    /// Algorithm tag file of a key file
+    pub fn file(key_file: &Path) -> CliTypedResult<PathBuf> {
+        append_file_extension(key_file, ALGORITHM_TAG_EXTENSION)
+    }
+
+    /// Loads the algorithm tag of a key file, if it has one
+    pub fn load(key_file: &Path) -> CliTypedResult<Option<AlgorithmTag>> {
+        let tag_file = Self::file(key_file)?;
+        if !tag_file.exists() {
+            return Ok(None);
+        }
+        let contents = read_from_file(&tag_file)?;
+        let tag = std::str::from_utf8(&contents)
+            .map_err(|_| CliError::UnableToParse("algorithm tag", "it isn't text".to_string()))?;
+        tag.parse()
+            .map(Some)
+            .map_err(|err| CliError::UnableToParse("algorithm tag", err))
+    }
+}
+
+impl std::fmt::Display for AlgorithmTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} key_type={} curve={} scheme={} encoding={}",
+            ALGORITHM_TAG_VERSION,
+            self.key_type,
+            self.key_type.curve(),
+            self.key_type.scheme(),
+            self.encoding
+        )
+    }
+}
+
+impl FromStr for AlgorithmTag {
+    type Err = String;
+
+    /// Parses a tag of this version, ignoring fields it doesn't know
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        match words.next() {
+            Some(ALGORITHM_TAG_VERSION) => {}
+            Some(version) => return Err(format!("Unsupported version '{}'", version)),
+            None => return Err("The tag is empty".to_string()),
+        }
+        let mut fields = HashMap::new();
+        for word in words {
+            let (name, value) = word
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid field '{}'", word))?;
+            fields.insert(name, value);
+        }
+        let field = |name: &str| {
+            fields
+                .get(name)
+                .copied()
+                .ok_or_else(|| format!("Missing field '{}'", name))
+        };
+
+        let key_type: KeyType = field("key_type")?.parse()?;
+        if field("curve")? != key_type.curve() || field("scheme")? != key_type.scheme() {
+            return Err(format!(
+                "The curve and scheme don't match the key type {}",
+                key_type
+            ));
+        }
+        let encoding: EncodingType = field("encoding")?.parse()?;
+        Ok(AlgorithmTag { key_type, encoding })
+    }
 }
 
 #[derive(Debug, Parser)]
 pub struct PublicKeyInputOptions {
-    /// Public key input file name
+    /// Public key input file name, or `-` to read from stdin
     #[clap(long, group = "public_key_input", parse(from_os_str))]
     public_key_file: Option<PathBuf>,
     /// Public key encoded in a type as shown in `encoding`
@@ -383,17 +1963,31 @@ pub struct PublicKeyInputOptions {
     public_key: Option<String>,
 }
 
+impl PublicKeyInputOptions {
+    /// Whether a public key was given at all
+    pub fn is_given(&self) -> bool {
+        self.public_key_file.is_some() || self.public_key.is_some()
+    }
+}
+
 impl ExtractPublicKey for PublicKeyInputOptions {
     fn extract_public_key(
         &self,
-        encoding: EncodingType,
+        encoding_options: &EncodingOptions,
         _profile: &str,
     ) -> CliTypedResult<Ed25519PublicKey> {
         if let Some(ref file) = self.public_key_file {
-            encoding.load_key("--public-key-file", file.as_path())
+            encoding_options
+                .key_file(file)
+                .with_key_type(KeyType::Ed25519)
+                .load("--public-key-file")
         } else if let Some(ref key) = self.public_key {
             let key = key.as_bytes().to_vec();
-            encoding.decode_key("--public-key", key)
+            encoding_options.encoding.decode_key_with_hrp(
+                "--public-key",
+                key,
+                &encoding_options.bech32_hrp,
+            )
         } else {
             Err(CliError::CommandArgumentError(
                 "One of ['--public-key', '--public-key-file'] must be used".to_string(),
@@ -402,24 +1996,128 @@ impl ExtractPublicKey for PublicKeyInputOptions {
     }
 }
 
+/// Where a key is read from: `-` for stdin, `file://<path>`, or `env://<VAR>` for an
+/// environment variable
+///
+/// Any other source is a file path, so a key file can always be given as a source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeySource {
+    Stdin,
+    File(PathBuf),
+    Env(String),
+}
+
+const FILE_SOURCE_PREFIX: &str = "file://";
+const ENV_SOURCE_PREFIX: &str = "env://";
+
+impl KeySource {
+    /// Source of a key file, which is stdin if the path is `-`
+    pub fn from_path(path: &Path) -> KeySource {
+        if path == Path::new(STDIN_PATH) {
+            KeySource::Stdin
+        } else {
+            KeySource::File(path.to_path_buf())
+        }
+    }
+
+    /// Reads the key, failing if it's larger than `max_size` bytes
+    pub fn read(&self, max_size: u64) -> CliTypedResult<Vec<u8>> {
+        self.read_with_stdin(std::io::stdin(), max_size)
+    }
+
+    /// Reads the key like [`KeySource::read`], with `stdin` standing in for stdin
+    pub fn read_with_stdin(&self, stdin: impl Read, max_size: u64) -> CliTypedResult<Vec<u8>> {
+        match self {
+            KeySource::Stdin => read_bounded(stdin, "stdin", max_size),
+            KeySource::File(path) => {
+                let file = std::fs::File::open(path).map_err(|err| {
+                    CliError::UnableToReadFile(path.display().to_string(), err.to_string())
+                })?;
+                read_bounded(file, &path.display().to_string(), max_size)
+            }
+            KeySource::Env(var) => {
+                let value = Zeroizing::new(std::env::var(var).map_err(|err| {
+                    CliError::CommandArgumentError(format!(
+                        "Unable to read key from environment variable {}: {}",
+                        var, err
+                    ))
+                })?);
+                read_bounded(value.as_bytes(), &self.to_string(), max_size)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for KeySource {
+    /// Shows the source with a file as its plain path, which parses back to the same source
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeySource::Stdin => f.write_str(STDIN_PATH),
+            KeySource::File(path) => write!(f, "{}", path.display()),
+            KeySource::Env(var) => write!(f, "{}{}", ENV_SOURCE_PREFIX, var),
+        }
+    }
+}
+
+impl FromStr for KeySource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(var) = s.strip_prefix(ENV_SOURCE_PREFIX) {
+            if var.is_empty() {
+                return Err(format!("Invalid key source '{}', expected env://<VAR>", s));
+            }
+            return Ok(KeySource::Env(var.to_string()));
+        }
+        let path = s.strip_prefix(FILE_SOURCE_PREFIX).unwrap_or(s);
+        if path.is_empty() {
+            return Err(format!(
+                "Invalid key source '{}', expected -, file://<path>, or env://<VAR>",
+                s
+            ));
+        }
+        Ok(KeySource::from_path(Path::new(path)))
+    }
+}
+
 #[derive(Debug, Parser)]
 pub struct PrivateKeyInputOptions {
-    /// Private key input file name
+    /// Private key input file name, or `-` to read from stdin
     #[clap(long, group = "private_key_input", parse(from_os_str))]
     private_key_file: Option<PathBuf>,
     /// Private key encoded in a type as shown in `encoding`
     #[clap(long, group = "private_key_input")]
     private_key: Option<String>,
+    /// Source to read the private key from: `-` for stdin, `file://<path>`, or `env://<VAR>`
+    #[clap(long, group = "private_key_input")]
+    key_source: Option<KeySource>,
+    /// Largest private key in bytes read from `--key-source` or `--private-key-file`
+    #[clap(long, default_value = "65536")]
+    max_key_size: u64,
+    // With `--key-store keyring`, `--private-key-file` names the keyring account to load the
+    // private key from, unless `--keyring-account` is given
+    #[clap(flatten)]
+    key_store_options: KeyStoreOptions,
+    #[clap(flatten)]
+    passphrase_options: PassphraseOptions,
 }
 
 impl PrivateKeyInputOptions {
+    /// Whether a private key was given on the command line
+    pub fn is_given(&self) -> bool {
+        self.private_key_file.is_some()
+            || self.private_key.is_some()
+            || self.key_source.is_some()
+            || self.key_store_options.is_keyring()
+    }
+
     /// Extract private key from CLI args with fallback to config
     pub fn extract_private_key(
         &self,
-        encoding: EncodingType,
+        encoding_options: &EncodingOptions,
         profile: &str,
     ) -> CliTypedResult<Ed25519PrivateKey> {
-        if let Some(key) = self.extract_private_key_cli(encoding)? {
+        if let Some(key) = self.extract_private_key_cli(encoding_options)? {
             Ok(key)
         } else if let Some(Some(private_key)) =
             CliConfig::load_profile(profile)?.map(|p| p.private_key)
@@ -427,7 +2125,8 @@ impl PrivateKeyInputOptions {
             Ok(private_key)
         } else {
             Err(CliError::CommandArgumentError(
-                "One of ['--private-key', '--private-key-file'] must be used".to_string(),
+                "One of ['--private-key', '--private-key-file', '--key-source'] must be used"
+                    .to_string(),
             ))
         }
     }
@@ -435,28 +2134,88 @@ impl PrivateKeyInputOptions {
     /// Extract private key from CLI args
     pub fn extract_private_key_cli(
         &self,
-        encoding: EncodingType,
+        encoding_options: &EncodingOptions,
     ) -> CliTypedResult<Option<Ed25519PrivateKey>> {
-        if let Some(ref file) = self.private_key_file {
-            Ok(Some(
-                encoding.load_key("--private-key-file", file.as_path())?,
-            ))
+        let encoding = encoding_options.encoding;
+        let bech32_hrp = &encoding_options.bech32_hrp;
+        if self.key_store_options.is_keyring() {
+            Ok(Some(self.load_from_keyring(encoding_options)?))
+        } else if let Some(ref file) = self.private_key_file {
+            Ok(Some(encoding.load_key_from_source(
+                "--private-key-file",
+                &KeySource::from_path(file),
+                self.max_key_size,
+                true,
+                &self.passphrase_options,
+                bech32_hrp,
+            )?))
+        } else if let Some(ref source) = self.key_source {
+            Ok(Some(encoding.load_key_from_source(
+                "--key-source",
+                source,
+                self.max_key_size,
+                true,
+                &self.passphrase_options,
+                bech32_hrp,
+            )?))
         } else if let Some(ref key) = self.private_key {
             let key = key.as_bytes().to_vec();
-            Ok(Some(encoding.decode_key("--private-key", key)?))
+            Ok(Some(encoding.decode_key_with_hrp(
+                "--private-key",
+                key,
+                bech32_hrp,
+            )?))
         } else {
             Ok(None)
         }
     }
+
+    /// Where the passphrase of an encrypted private key comes from, for other private keys of
+    /// the same command
+    pub fn passphrase_options(&self) -> &PassphraseOptions {
+        &self.passphrase_options
+    }
+
+    /// Loads the private key from the keyring entry of `--private-key-file` or
+    /// `--keyring-account`, with `--key-store keyring`
+    fn load_from_keyring(
+        &self,
+        encoding_options: &EncodingOptions,
+    ) -> CliTypedResult<Ed25519PrivateKey> {
+        if self.private_key.is_some() || self.key_source.is_some() {
+            return Err(CliError::CommandArgumentError(
+                "--key-store keyring only loads --private-key-file from the keyring".to_string(),
+            ));
+        }
+        let default_account =
+            match (
+                &self.private_key_file,
+                &self.key_store_options.keyring_account,
+            ) {
+                (Some(file), _) => file.display().to_string(),
+                (None, Some(account)) => account.clone(),
+                (None, None) => return Err(CliError::CommandArgumentError(
+                    "--key-store keyring needs --private-key-file or --keyring-account to name \
+                    the key"
+                        .to_string(),
+                )),
+            };
+        encoding_options.encoding.load_key_from_store(
+            "--private-key-file",
+            &self.key_store_options.keyring(default_account),
+            &self.passphrase_options,
+            &encoding_options.bech32_hrp,
+        )
+    }
 }
 
 impl ExtractPublicKey for PrivateKeyInputOptions {
     fn extract_public_key(
         &self,
-        encoding: EncodingType,
+        encoding_options: &EncodingOptions,
         profile: &str,
     ) -> CliTypedResult<Ed25519PublicKey> {
-        self.extract_private_key(encoding, profile)
+        self.extract_private_key(encoding_options, profile)
             .map(|private_key| private_key.public_key())
     }
 }
@@ -464,16 +2223,16 @@ impl ExtractPublicKey for PrivateKeyInputOptions {
 pub trait ExtractPublicKey {
     fn extract_public_key(
         &self,
-        encoding: EncodingType,
+        encoding_options: &EncodingOptions,
         profile: &str,
     ) -> CliTypedResult<Ed25519PublicKey>;
 
     fn extract_x25519_public_key(
         &self,
-        encoding: EncodingType,
+        encoding_options: &EncodingOptions,
         profile: &str,
     ) -> CliTypedResult<x25519::PublicKey> {
-        let key = self.extract_public_key(encoding, profile)?;
+        let key = self.extract_public_key(encoding_options, profile)?;
         x25519::PublicKey::from_ed25519_public_bytes(&key.to_bytes()).map_err(|err| {
             CliError::UnexpectedError(format!("Failed to convert ed25519 to x25519 {:?}", err))
         })
@@ -643,18 +2402,35 @@ pub trait CliCommand<T: Serialize + Send>: Sized + Send {
     /// Executes the command, returning a command specific type
     async fn execute(self) -> CliTypedResult<T>;
 
+    /// Whether errors are shown as JSON on stderr, as with `--output-format json`
+    fn json_errors(&self) -> bool {
+        false
+    }
+
     /// Executes the command, and serializes it to the common JSON output type
     async fn execute_serialized(self) -> CliResult {
         let command_name = self.command_name();
+        let json_errors = self.json_errors();
         let start_time = Instant::now();
-        to_common_result(command_name, start_time, self.execute().await).await
+        to_common_result(command_name, start_time, self.execute().await, json_errors).await
+    }
+
+    /// Executes the command, and throws away Ok(result) for no output at all
+    async fn execute_serialized_without_output(self) -> CliResult {
+        let command_name = self.command_name();
+        let json_errors = self.json_errors();
+        let start_time = Instant::now();
+        to_common_result(command_name, start_time, self.execute().await, json_errors)
+            .await
+            .map(|_| String::new())
     }
 
     /// Executes the command, and throws away Ok(result) for the string Success
     async fn execute_serialized_success(self) -> CliResult {
         let command_name = self.command_name();
+        let json_errors = self.json_errors();
         let start_time = Instant::now();
-        to_common_success_result(command_name, start_time, self.execute().await).await
+        to_common_success_result(command_name, start_time, self.execute().await, json_errors).await
     }
 }
 
@@ -2,10 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    common::types::{CliError, CliTypedResult, PromptOptions},
+    common::types::{CliError, CliFailure, CliTypedResult, PromptOptions},
     CliResult,
 };
 use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey};
+use aptos_logger::debug;
 use aptos_rest_client::{Client, Transaction};
 use aptos_sdk::{transaction_builder::TransactionFactory, types::LocalAccount};
 use aptos_telemetry::constants::APTOS_CLI_PUSH_METRICS;
@@ -17,38 +18,82 @@ use itertools::Itertools;
 use move_deps::move_core_types::account_address::AccountAddress;
 use reqwest::Url;
 use serde::Serialize;
+use sha2::{Digest, Sha256};
 use shadow_rs::shadow;
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
 use std::{
     collections::{BTreeMap, HashMap},
     env,
     fs::OpenOptions,
-    io::Write,
-    os::unix::fs::OpenOptionsExt,
+    io::{BufRead, Read, Write},
     path::{Path, PathBuf},
     str::FromStr,
-    time::{Duration, Instant},
+    sync::mpsc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, Zeroizing};
 
 shadow!(build);
 
 /// Prompts for confirmation until a yes or no is given explicitly
+///
+/// Stdin ending without an answer is a no, rather than prompting forever.
 pub fn prompt_yes(prompt: &str) -> bool {
-    let mut result: Result<bool, ()> = Err(());
+    prompt_yes_from(prompt, &mut std::io::stdin().lock()).unwrap_or(false)
+}
+
+/// Prompts for confirmation like `prompt_yes`, giving up after `timeout`
+///
+/// Returns `None` if no yes or no is given in time, or stdin ends first.
+pub fn prompt_yes_with_timeout(prompt: &str, timeout: Duration) -> Option<bool> {
+    let prompt = prompt.to_string();
+    answer_within(timeout, move || {
+        let stdin = std::io::stdin();
+        let mut input = stdin.lock();
+        prompt_yes_from(&prompt, &mut input)
+    })
+}
 
-    // Read input until a yes or a no is given
-    while result.is_err() {
+/// Prompts for confirmation until a yes or no is read from `input`, or `input` ends
+pub(crate) fn prompt_yes_from(prompt: &str, input: &mut impl BufRead) -> Option<bool> {
+    loop {
         println!("{} [yes/no] >", prompt);
-        let mut input = String::new();
-        if std::io::stdin().read_line(&mut input).is_err() {
-            continue;
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(_) => continue,
         }
-        result = match input.trim().to_lowercase().as_str() {
-            "yes" | "y" => Ok(true),
-            "no" | "n" => Ok(false),
-            _ => Err(()),
-        };
+        if let Ok(answer) = parse_yes_no(&line) {
+            return Some(answer);
+        }
+    }
+}
+
+/// Waits up to `timeout` for the answer of `answer`, which reads it on another thread
+///
+/// A thread blocked reading is left behind if there's no answer in time, as reads can't be
+/// cancelled.  Its answer is thrown away if it ever comes.
+pub(crate) fn answer_within(
+    timeout: Duration,
+    answer: impl FnOnce() -> Option<bool> + Send + 'static,
+) -> Option<bool> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        // Fails only if the wait has already timed out
+        let _ = sender.send(answer());
+    });
+    receiver.recv_timeout(timeout).ok().flatten()
+}
+
+fn parse_yes_no(input: &str) -> Result<bool, ()> {
+    match input.trim().to_lowercase().as_str() {
+        "yes" | "y" => Ok(true),
+        "no" | "n" => Ok(false),
+        _ => Err(()),
     }
-    result.unwrap()
 }
 
 /// Convert any successful response to Success
@@ -56,22 +101,26 @@ pub async fn to_common_success_result<T>(
     command: &str,
     start_time: Instant,
     result: CliTypedResult<T>,
+    json_errors: bool,
 ) -> CliResult {
-    to_common_result(command, start_time, result.map(|_| "Success")).await
+    to_common_result(command, start_time, result.map(|_| "Success"), json_errors).await
 }
 
 /// For pretty printing outputs in JSON
+///
+/// With `json_errors`, an error is a [`JsonError`] to be printed to stderr instead.
 pub async fn to_common_result<T: Serialize>(
     command: &str,
     start_time: Instant,
     result: CliTypedResult<T>,
+    json_errors: bool,
 ) -> CliResult {
     let latency = start_time.elapsed();
     let is_err = result.is_err();
-    let error = if let Err(ref e) = result {
-        e.to_str()
+    let (error, exit_code) = if let Err(ref e) = result {
+        (e.to_str(), e.exit_code())
     } else {
-        "None"
+        ("None", 0)
     };
     let metrics = collect_metrics(command, !is_err, latency, error);
     aptos_telemetry::send_env_data(
@@ -80,10 +129,21 @@ pub async fn to_common_result<T: Serialize>(
         metrics,
     )
     .await;
+    if let (Err(err), true) = (&result, json_errors) {
+        return Err(CliFailure {
+            output: serde_json::to_string_pretty(&JsonError::from(err)).unwrap(),
+            exit_code,
+            to_stderr: true,
+        });
+    }
     let result: ResultWrapper<T> = result.into();
     let string = serde_json::to_string_pretty(&result).unwrap();
     if is_err {
-        Err(string)
+        Err(CliFailure {
+            output: string,
+            exit_code,
+            to_stderr: false,
+        })
     } else {
         Ok(string)
     }
@@ -151,24 +211,78 @@ fn collect_metrics(
 ///   "Error":"Failed to run command"
 /// }
 ///
+/// An error with a result of its own, e.g. the summary of a failed `key batch-verify`, has that
+/// result as the error instead of a message.
 #[derive(Debug, Serialize)]
 enum ResultWrapper<T> {
     Result(T),
-    Error(String),
+    Error(serde_json::Value),
 }
 
 impl<T> From<CliTypedResult<T>> for ResultWrapper<T> {
     fn from(result: CliTypedResult<T>) -> Self {
         match result {
             Ok(inner) => ResultWrapper::Result(inner),
-            Err(inner) => ResultWrapper::Error(inner.to_string()),
+            Err(CliError::BatchVerifyFailed(summary)) => ResultWrapper::Error(summary),
+            Err(inner) => ResultWrapper::Error(serde_json::Value::String(inner.to_string())),
+        }
+    }
+}
+
+/// An error for tools wrapping the CLI, as printed to stderr with `--output-format json`
+///
+/// {
+///   "error":{
+///     "kind":"CommandArgumentError",
+///     "message":"Invalid arguments: ..."
+///   }
+/// }
+///
+#[derive(Debug, Serialize)]
+pub struct JsonError {
+    pub error: JsonErrorInfo,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonErrorInfo {
+    /// Kind of the error, from [`CliError::kind`]
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl From<&CliError> for JsonError {
+    fn from(err: &CliError) -> Self {
+        JsonError {
+            error: JsonErrorInfo {
+                kind: err.kind(),
+                message: err.to_string(),
+            },
         }
     }
 }
 
 /// Checks if a file exists, being overridden by `PromptOptions`
+///
+/// When stdin isn't a terminal there's no one to answer the prompt, so an existing file is an
+/// error unless `PromptOptions` answers it.
 pub fn check_if_file_exists(file: &Path, prompt_options: PromptOptions) -> CliTypedResult<()> {
+    check_if_file_exists_with_terminal(file, prompt_options, atty::is(atty::Stream::Stdin))
+}
+
+/// Checks if a file exists, as `check_if_file_exists` with a known `is_terminal`
+pub(crate) fn check_if_file_exists_with_terminal(
+    file: &Path,
+    prompt_options: PromptOptions,
+    is_terminal: bool,
+) -> CliTypedResult<()> {
     if file.exists() {
+        prompt_options.check_no_overwrite(&format!("{:?}", file.as_os_str()))?;
+        if !is_terminal && !prompt_options.assume_yes && !prompt_options.assume_no {
+            return Err(CliError::CommandArgumentError(format!(
+                "{:?} already exists; pass --assume-yes to overwrite it",
+                file.as_os_str()
+            )));
+        }
         prompt_yes_with_override(
             &format!(
                 "{:?} already exists, are you sure you want to overwrite it?",
@@ -181,19 +295,128 @@ pub fn check_if_file_exists(file: &Path, prompt_options: PromptOptions) -> CliTy
     Ok(())
 }
 
+/// Copies an existing file to `file.bak-<unix seconds>` before it's overwritten, returning the
+/// backup file, or `None` if there's no file
+///
+/// The backup is written with [`write_backup_file`], so another backup in the same second gets
+/// a `-N` suffix and no backup is replaced.  It has the permissions of the file, e.g. a private
+/// key stays only readable by the user.
+pub fn backup_existing_file(file: &Path) -> CliTypedResult<Option<PathBuf>> {
+    let name = file.display().to_string();
+    let mut opened = match std::fs::File::open(file) {
+        Ok(opened) => opened,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(CliError::UnableToReadFile(name, err.to_string())),
+    };
+    let mut bytes = Zeroizing::new(Vec::new());
+    opened
+        .read_to_end(&mut bytes)
+        .map_err(|err| CliError::IO(name.clone(), err))?;
+    #[cfg(unix)]
+    let mode = opened
+        .metadata()
+        .map_err(|err| CliError::IO(name.clone(), err))?
+        .permissions()
+        .mode()
+        & 0o777;
+    #[cfg(not(unix))]
+    let mode = 0o600;
+
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+        .as_secs();
+    write_backup_file(file, &format!("bak-{}", secs), &bytes, mode).map(Some)
+}
+
+/// Prompts unless `PromptOptions` answers, aborting on a no or on no answer in time
 pub fn prompt_yes_with_override(prompt: &str, prompt_options: PromptOptions) -> CliTypedResult<()> {
-    if prompt_options.assume_no || (!prompt_options.assume_yes && !prompt_yes(prompt)) {
-        Err(CliError::AbortedError)
-    } else {
+    if prompt_options.assume_no {
+        return Err(CliError::AbortedError);
+    }
+    if prompt_options.assume_yes {
+        return Ok(());
+    }
+    let answer = match prompt_options.prompt_timeout {
+        Some(secs) => prompt_yes_with_timeout(prompt, Duration::from_secs(secs)).unwrap_or(false),
+        None => prompt_yes(prompt),
+    };
+    if answer {
         Ok(())
+    } else {
+        Err(CliError::AbortedError)
     }
 }
 
 pub fn read_from_file(path: &Path) -> CliTypedResult<Vec<u8>> {
+    debug!("Reading {}", path.display());
     std::fs::read(path)
         .map_err(|e| CliError::UnableToReadFile(format!("{}", path.display()), e.to_string()))
 }
 
+/// Path given in place of a file to read from stdin
+pub const STDIN_PATH: &str = "-";
+
+/// Reads a file, or reads stdin until EOF if the path is `-`
+pub fn read_from_file_or_stdin(path: &Path) -> CliTypedResult<Vec<u8>> {
+    if path.as_os_str() == STDIN_PATH {
+        debug!("Reading stdin");
+        let mut bytes = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|e| CliError::IO("stdin".to_string(), e))?;
+        Ok(bytes)
+    } else {
+        read_from_file(path)
+    }
+}
+
+/// Default limit on the size of a key read from a key source
+pub const MAX_KEY_SIZE: u64 = 64 * 1024;
+
+/// Reads until EOF, failing if there are more than `max_size` bytes
+///
+/// Pipes and environment variables have no size to check up front, so the read itself is
+/// bounded rather than trusting the input to end.
+pub fn read_bounded(reader: impl Read, name: &str, max_size: u64) -> CliTypedResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader
+        .take(max_size.saturating_add(1))
+        .read_to_end(&mut bytes)
+        .map_err(|err| CliError::IO(name.to_string(), err))?;
+    if bytes.len() as u64 > max_size {
+        bytes.zeroize();
+        return Err(CliError::CommandArgumentError(format!(
+            "Key from {} is larger than the limit of {} bytes",
+            name, max_size
+        )));
+    }
+    Ok(bytes)
+}
+
+/// Path that stands for stdout when writing, as `-` stands for stdin when reading
+pub const STDOUT_PATH: &str = "-";
+
+/// Whether the path stands for stdout
+pub fn is_stdout_path(path: &Path) -> bool {
+    path.as_os_str() == STDOUT_PATH
+}
+
+/// Writes to a file, or to stdout if the path is `-`
+///
+/// Files are written with the default permissions, so confidential files must be written with
+/// `write_to_user_only_file` instead.
+pub fn write_to_file_or_stdout(path: &Path, name: &str, bytes: &[u8]) -> CliTypedResult<()> {
+    if !is_stdout_path(path) {
+        return write_to_file(path, name, bytes);
+    }
+    let mut stdout = std::io::stdout();
+    stdout
+        .write_all(bytes)
+        .and_then(|_| stdout.flush())
+        .map_err(|e| CliError::IO(name.to_string(), e))
+}
+
 /// Write a `&[u8]` to a file
 pub fn write_to_file(path: &Path, name: &str, bytes: &[u8]) -> CliTypedResult<()> {
     write_to_file_with_opts(path, name, bytes, &mut OpenOptions::new())
@@ -201,10 +424,75 @@ pub fn write_to_file(path: &Path, name: &str, bytes: &[u8]) -> CliTypedResult<()
 
 /// Write a User only read / write file
 pub fn write_to_user_only_file(path: &Path, name: &str, bytes: &[u8]) -> CliTypedResult<()> {
+    write_to_file_with_mode(path, name, bytes, 0o600)
+}
+
+/// Write a file readable by anyone, but only writable by the User
+pub fn write_to_public_file(path: &Path, name: &str, bytes: &[u8]) -> CliTypedResult<()> {
+    write_to_file_with_mode(path, name, bytes, 0o644)
+}
+
+/// Write a file readable by anyone like `write_to_public_file`, streaming its contents from
+/// `write`
+///
+/// The contents are streamed to a temporary file beside the file, which is only renamed over it
+/// once `write` succeeds, so a failed write never leaves an empty or partial file behind.
+pub fn write_to_public_file_with(
+    path: &Path,
+    name: &str,
+    write: impl FnOnce(&mut dyn Write) -> CliTypedResult<()>,
+) -> CliTypedResult<()> {
+    let temp_path = append_file_extension(path, &format!("tmp-{}", std::process::id()))?;
+    let result = write_to_file_with(
+        &temp_path,
+        name,
+        &mut open_options_with_mode(&temp_path, name, 0o644)?,
+        write,
+    )
+    .and_then(|()| {
+        std::fs::rename(&temp_path, path).map_err(|e| CliError::IO(io_name(name, path), e))
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Write a file with the unix permissions `mode`, even if the file already exists
+///
+/// Other platforms have no such permissions, so a warning is printed for files that should be
+/// restricted to the User.
+pub fn write_to_file_with_mode(
+    path: &Path,
+    name: &str,
+    bytes: &[u8],
+    mode: u32,
+) -> CliTypedResult<()> {
+    let mut opts = open_options_with_mode(path, name, mode)?;
+    write_to_file_with_opts(path, name, bytes, &mut opts)
+}
+
+/// Options for opening a file with the unix permissions `mode`, restricting the file first if
+/// it already exists
+fn open_options_with_mode(path: &Path, name: &str, mode: u32) -> CliTypedResult<OpenOptions> {
     let mut opts = OpenOptions::new();
     #[cfg(unix)]
-    opts.mode(0o600);
-    write_to_file_with_opts(path, name, bytes, &mut opts)
+    {
+        // The mode only applies to new files, so existing files are restricted before writing
+        opts.mode(mode);
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+                .map_err(|e| CliError::IO(io_name(name, path), e))?;
+        }
+    }
+    #[cfg(not(unix))]
+    if mode & 0o077 == 0 {
+        eprintln!(
+            "Warning: {} can't be restricted to the current user on this platform",
+            path.display()
+        );
+    }
+    Ok(opts)
 }
 
 /// Write a `&[u8]` to a file with the given options
@@ -214,21 +502,188 @@ pub fn write_to_file_with_opts(
     bytes: &[u8],
     opts: &mut OpenOptions,
 ) -> CliTypedResult<()> {
+    write_to_file_with(path, name, opts, |writer| {
+        writer
+            .write_all(bytes)
+            .map_err(|e| CliError::IO(name.to_string(), e))
+    })
+}
+
+/// Names a file in IO errors by what it holds and by its path, e.g. `private key at keys/key`,
+/// so errors say which of several files failed
+///
+/// Names that are already the path aren't repeated.
+pub fn io_name(name: &str, path: &Path) -> String {
+    let path = path.display().to_string();
+    if name == path {
+        path
+    } else {
+        format!("{} at {}", name, path)
+    }
+}
+
+/// Write a file with the given options, streaming its contents from `write`
+///
+/// Writes go straight to the file, so large contents can be written in chunks without holding
+/// all of them in memory, and no copy of confidential contents is left in a buffer.  IO errors
+/// of `write` named `name` are named by the path too.
+pub fn write_to_file_with(
+    path: &Path,
+    name: &str,
+    opts: &mut OpenOptions,
+    write: impl FnOnce(&mut dyn Write) -> CliTypedResult<()>,
+) -> CliTypedResult<()> {
+    debug!("Writing {} to {}", name, path.display());
     let mut file = opts
         .write(true)
         .create(true)
         .truncate(true)
         .open(path)
-        .map_err(|e| CliError::IO(name.to_string(), e))?;
-    file.write_all(bytes)
-        .map_err(|e| CliError::IO(name.to_string(), e))
+        .map_err(|e| match path.parent() {
+            // A missing directory is otherwise reported as a missing file
+            Some(parent)
+                if e.kind() == std::io::ErrorKind::NotFound
+                    && !parent.as_os_str().is_empty()
+                    && !parent.is_dir() =>
+            {
+                CliError::IO(
+                    format!(
+                        "{} (parent directory {} does not exist)",
+                        io_name(name, path),
+                        parent.display()
+                    ),
+                    e,
+                )
+            }
+            _ => CliError::IO(io_name(name, path), e),
+        })?;
+    write(&mut file).map_err(|err| match err {
+        CliError::IO(err_name, e) if err_name == name => CliError::IO(io_name(name, path), e),
+        err => err,
+    })
 }
 
-/// Appends a file extension to a `Path` without overwriting the original extension.
-pub fn append_file_extension(
+/// Replaces a file with `bytes` so it's never left partly written, with the unix permissions
+/// `mode`
+///
+/// The bytes are written to a temporary file beside the file, synced to disk, and renamed over
+/// the file, which is atomic on the same file system.  The temporary file is removed if the
+/// write fails.
+pub fn replace_file_atomically(
+    path: &Path,
+    name: &str,
+    bytes: &[u8],
+    mode: u32,
+) -> CliTypedResult<()> {
+    debug!("Replacing {} at {}", name, path.display());
+    let temp_path = append_file_extension(path, &format!("tmp-{}", std::process::id()))?;
+    write_new_file_synced(&temp_path, bytes, mode)
+        .map_err(|e| CliError::IO(io_name(name, &temp_path), e))?;
+    if let Err(e) = std::fs::rename(&temp_path, path) {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(CliError::IO(io_name(name, path), e));
+    }
+
+    // The rename is only durable once the directory holding it is synced too
+    #[cfg(unix)]
+    {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        std::fs::File::open(parent)
+            .and_then(|dir| dir.sync_all())
+            .map_err(|e| CliError::IO(io_name(name, path), e))?;
+    }
+    Ok(())
+}
+
+/// Writes a backup of a file that's about to be replaced to `file.<extension>`, or to
+/// `file.<extension>-N` if that backup already exists, returning the backup file
+///
+/// Backups are created with `create_new`, so an existing backup is never replaced, even one
+/// created at the same moment by another process.
+pub fn write_backup_file(
     file: &Path,
-    appended_extension: &'static str,
+    extension: &str,
+    bytes: &[u8],
+    mode: u32,
 ) -> CliTypedResult<PathBuf> {
+    let mut backup_file = append_file_extension(file, extension)?;
+    let mut count = 1;
+    loop {
+        match write_new_file_synced(&backup_file, bytes, mode) {
+            Ok(()) => return Ok(backup_file),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                count += 1;
+                backup_file = append_file_extension(file, &format!("{}-{}", extension, count))?;
+            }
+            Err(e) => return Err(CliError::IO(io_name("backup", &backup_file), e)),
+        }
+    }
+}
+
+/// Writes `bytes` to a new file with the unix permissions `mode`, and syncs it to disk
+///
+/// An existing file fails with `AlreadyExists` rather than being replaced, and a file that
+/// fails to be written is removed.
+fn write_new_file_synced(path: &Path, bytes: &[u8], mode: u32) -> std::io::Result<()> {
+    let mut opts = OpenOptions::new();
+    opts.write(true).create_new(true);
+    #[cfg(unix)]
+    opts.mode(mode);
+    #[cfg(not(unix))]
+    let _ = mode;
+    let mut file = opts.open(path)?;
+    let result = file.write_all(bytes).and_then(|_| file.sync_all());
+    if result.is_err() {
+        let _ = std::fs::remove_file(path);
+    }
+    result
+}
+
+/// Compares crypto material in constant time, so the time taken doesn't tell how much matched
+///
+/// Only the lengths leak, and they're never secret.
+pub fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.ct_eq(right).into()
+}
+
+/// Extension of the checksum file of a key file, e.g. `mykey.key.sha256`
+pub const CHECKSUM_EXTENSION: &str = "sha256";
+
+/// Hex SHA-256 of the bytes of a key file, as saved to its checksum file
+pub fn checksum(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Checks the bytes of a key file against its checksum file, if it has one
+///
+/// Key files without a checksum file, and keys read from stdin, aren't checked.  A mismatch is
+/// a [`CliError::ChecksumMismatch`] with an exit code of its own, rather than an
+/// `UnexpectedError`, so it can't be mistaken for keys that differ in `key diff`.
+pub fn verify_checksum(path: &Path, bytes: &[u8]) -> CliTypedResult<()> {
+    if path == Path::new(STDIN_PATH) {
+        return Ok(());
+    }
+    let checksum_file = append_file_extension(path, CHECKSUM_EXTENSION)?;
+    if !checksum_file.exists() {
+        return Ok(());
+    }
+    let expected = String::from_utf8(read_from_file(&checksum_file)?)
+        .map_err(|err| CliError::UnableToParse("checksum file", err.to_string()))?;
+    if !expected.trim().eq_ignore_ascii_case(&checksum(bytes)) {
+        return Err(CliError::ChecksumMismatch(format!(
+            "{} doesn't match {}, it may be corrupted or tampered with",
+            path.display(),
+            checksum_file.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Appends a file extension to a `Path` without overwriting the original extension.
+pub fn append_file_extension(file: &Path, appended_extension: &str) -> CliTypedResult<PathBuf> {
     let extension = file
         .extension()
         .map(|extension| extension.to_str().unwrap_or_default());
@@ -239,6 +694,27 @@ pub fn append_file_extension(
     }
 }
 
+/// Extensions of private key files, replaced rather than appended to when naming the public
+/// key file, so `mykey.key` has the public key file `mykey.pub`
+pub const PRIVATE_KEY_EXTENSIONS: &[&str] = &["key", "priv"];
+
+/// Replaces the extension of a `Path` if it's one of `replaced_extensions`, and otherwise
+/// appends the extension as `append_file_extension` does.
+///
+/// Only the file name is looked at, so dots in directory names are left alone.
+pub fn replace_file_extension(
+    file: &Path,
+    replaced_extensions: &[&str],
+    extension: &str,
+) -> CliTypedResult<PathBuf> {
+    match file.extension().and_then(|extension| extension.to_str()) {
+        Some(original) if replaced_extensions.contains(&original) => {
+            Ok(file.with_extension(extension))
+        }
+        _ => append_file_extension(file, extension),
+    }
+}
+
 /// Retrieves sequence number from the rest client
 pub async fn get_sequence_number(
     client: &aptos_rest_client::Client,
@@ -332,6 +808,38 @@ pub fn read_line(input_name: &'static str) -> CliTypedResult<String> {
     Ok(input_buf)
 }
 
+/// Prompts for a passphrase on the terminal, and reads it without echoing it
+///
+/// Without a terminal, e.g. in a script, the passphrase has to be given with
+/// `--passphrase-env` or `--passphrase-file` instead.
+pub fn read_passphrase(prompt: &str) -> CliTypedResult<Zeroizing<String>> {
+    rpassword::prompt_password(format!("{} > ", prompt))
+        .map(Zeroizing::new)
+        .map_err(|err| {
+            CliError::CommandArgumentError(format!(
+                "Unable to read the passphrase from the terminal, use --passphrase-env or \
+                --passphrase-file without one: {}",
+                err
+            ))
+        })
+}
+
+/// Prompts for a new passphrase twice, failing if they don't match or are empty
+pub fn read_new_passphrase() -> CliTypedResult<Zeroizing<String>> {
+    let passphrase = read_passphrase("Enter a passphrase to encrypt the private key")?;
+    if passphrase.is_empty() {
+        return Err(CliError::CommandArgumentError(
+            "Passphrase must not be empty".to_string(),
+        ));
+    }
+    if *passphrase != *read_passphrase("Enter the passphrase again")? {
+        return Err(CliError::CommandArgumentError(
+            "Passphrases don't match".to_string(),
+        ));
+    }
+    Ok(passphrase)
+}
+
 /// Fund account (and possibly create it) from a faucet
 pub async fn fund_account(
     faucet_url: Url,
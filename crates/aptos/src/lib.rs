@@ -11,12 +11,23 @@ pub mod move_tool;
 pub mod op;
 
 use crate::common::types::{CliCommand, CliResult};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 /// CLI tool for interacting with the Aptos blockchain and nodes
 ///
 #[derive(Parser)]
 #[clap(name = "aptos", author, version, propagate_version = true)]
+pub struct Cli {
+    /// Log the files read and written, and the encodings and key types of keys, to stderr
+    ///
+    /// Key material itself is never logged.
+    #[clap(short, long, global = true)]
+    pub verbose: bool,
+    #[clap(subcommand)]
+    pub tool: Tool,
+}
+
+#[derive(Subcommand)]
 pub enum Tool {
     #[clap(subcommand)]
     Account(account::AccountTool),
@@ -5,30 +5,45 @@
 
 #![forbid(unsafe_code)]
 
-use aptos::Tool;
+use aptos::Cli;
 use aptos_logger::Level;
 use clap::Parser;
 use std::process::exit;
 
 #[tokio::main]
 async fn main() {
+    let cli = Cli::parse();
+
     let mut logger = aptos_logger::Logger::new();
     logger
         .channel_size(1000)
         .is_async(false)
-        .level(Level::Warn)
+        .level(if cli.verbose {
+            Level::Debug
+        } else {
+            Level::Warn
+        })
         .read_env();
     logger.build();
 
     // Run the corresponding tools
-    let result = Tool::parse().execute().await;
+    let result = cli.tool.execute().await;
 
     // At this point, we'll want to print and determine whether to exit for an error code
     match result {
-        Ok(inner) => println!("{}", inner),
+        Ok(inner) => {
+            // Some commands print their own output, and have nothing left to show
+            if !inner.is_empty() {
+                println!("{}", inner)
+            }
+        }
         Err(inner) => {
-            println!("{}", inner);
-            exit(1);
+            if inner.to_stderr {
+                eprintln!("{}", inner);
+            } else {
+                println!("{}", inner);
+            }
+            exit(inner.exit_code);
         }
     }
 }
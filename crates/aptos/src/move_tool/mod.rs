@@ -270,10 +270,10 @@ impl CliCommand<TransactionSummary> for PublishPackage {
         let compiled_payload = TransactionPayload::ModuleBundle(ModuleBundle::new(compiled_units));
 
         // Now that it's compiled, lets send it
-        let sender_key = self.write_options.private_key_options.extract_private_key(
-            self.encoding_options.encoding,
-            &self.profile_options.profile,
-        )?;
+        let sender_key = self
+            .write_options
+            .private_key_options
+            .extract_private_key(&self.encoding_options, &self.profile_options.profile)?;
 
         submit_transaction(
             self.write_options
@@ -352,10 +352,9 @@ impl CliCommand<TransactionSummary> for RunFunction {
             self.write_options
                 .chain_id(&self.profile_options.profile)
                 .await?,
-            self.write_options.private_key_options.extract_private_key(
-                self.encoding_options.encoding,
-                &self.profile_options.profile,
-            )?,
+            self.write_options
+                .private_key_options
+                .extract_private_key(&self.encoding_options, &self.profile_options.profile)?,
             TransactionPayload::ScriptFunction(script_function),
             self.write_options.max_gas,
         )
@@ -0,0 +1,58 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! JSON Web Keys of `ed25519` public keys
+//!
+//! An `ed25519` public key is an octet key pair (OKP) JWK as described in RFC 8037, with the key
+//! in `x` as unpadded base64url, as RFC 7515 requires.
+
+use crate::common::types::{CliError, CliTypedResult};
+use aptos_crypto::ed25519::Ed25519PublicKey;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+/// Key type of an octet key pair
+pub const JWK_KEY_TYPE: &str = "OKP";
+/// Curve of an `ed25519` key
+pub const JWK_ED25519_CURVE: &str = "Ed25519";
+
+/// A public JSON Web Key
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct Jwk {
+    pub kty: String,
+    pub crv: String,
+    /// The public key in unpadded base64url
+    pub x: String,
+    /// Key ID, to tell keys of a set apart
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+}
+
+impl Jwk {
+    /// The JWK of an `ed25519` public key, with an optional key ID
+    pub fn from_ed25519_public_key(public_key: &Ed25519PublicKey, kid: Option<String>) -> Jwk {
+        Jwk {
+            kty: JWK_KEY_TYPE.to_string(),
+            crv: JWK_ED25519_CURVE.to_string(),
+            x: base64::encode_config(public_key.to_bytes(), base64::URL_SAFE_NO_PAD),
+            kid,
+        }
+    }
+
+    /// Parses the `ed25519` public key of a JWK, failing if it's any other type of key
+    pub fn to_ed25519_public_key(&self) -> CliTypedResult<Ed25519PublicKey> {
+        if self.kty != JWK_KEY_TYPE || self.crv != JWK_ED25519_CURVE {
+            return Err(CliError::UnableToParse(
+                "JWK",
+                format!(
+                    "expected a {} key on {}, found a {} key on {}",
+                    JWK_KEY_TYPE, JWK_ED25519_CURVE, self.kty, self.crv
+                ),
+            ));
+        }
+        let bytes = base64::decode_config(&self.x, base64::URL_SAFE_NO_PAD)
+            .map_err(|err| CliError::UnableToParse("JWK", err.to_string()))?;
+        Ed25519PublicKey::try_from(bytes.as_slice())
+            .map_err(|err| CliError::UnableToParse("JWK", format!("Invalid key {:?}", err)))
+    }
+}
@@ -3,39 +3,227 @@
 
 use crate::{
     common::{
+        audit::AuditLogOptions,
+        encryption,
+        key_store::{FileKeyStore, KeyStore, KeyStoreOptions, KeyStoreType},
         types::{
-            CliError, CliTypedResult, EncodingOptions, EncodingType, ExtractPublicKey, KeyType,
-            PrivateKeyInputOptions, ProfileOptions, SaveFile,
+            describe_key, parse_by_name, AlgorithmTag, ChecksumOptions, CliError, CliTypedResult,
+            EncodingOptions, EncodingType, ExtractPublicKey, HexCase, IoOptions, KeyEncodeOptions,
+            KeyFile, KeyFormat, KeyType, KeyTypeSelection, PassphraseOptions,
+            PrivateKeyInputOptions, ProfileOptions, PromptOptions, PublicKeyInputOptions, SaveFile,
+            SecpFormat, DEFAULT_BECH32_HRP,
         },
-        utils::{append_file_extension, check_if_file_exists, write_to_file},
+        utils::{
+            append_file_extension, backup_existing_file, check_if_file_exists, checksum,
+            constant_time_eq, is_stdout_path, read_from_file, read_from_file_or_stdin,
+            replace_file_atomically, replace_file_extension, write_backup_file, write_to_file,
+            write_to_public_file, write_to_user_only_file, CHECKSUM_EXTENSION,
+            PRIVATE_KEY_EXTENSIONS,
+        },
+    },
+    op::{
+        jwk::Jwk,
+        mnemonic::{self, DerivationPath, MNEMONIC_EXTENSION},
+        ssh, vanity,
     },
     CliCommand, CliResult,
 };
-use aptos_config::config::{Peer, PeerRole};
-use aptos_crypto::{ed25519, x25519, PrivateKey, Uniform, ValidCryptoMaterial};
-use aptos_types::account_address::{from_identity_public_key, AccountAddress};
+use aptos_config::config::{Identity, IdentityBlob, Peer, PeerRole};
+use aptos_crypto::{
+    ed25519,
+    multi_ed25519::{MultiEd25519PublicKey, MultiEd25519Signature},
+    secp256k1, secp256r1, x25519, PrivateKey, Signature, Uniform, ValidCryptoMaterial,
+};
+use aptos_logger::debug;
+use aptos_types::{
+    account_address::{from_identity_public_key, AccountAddress},
+    transaction::authenticator::AuthenticationKey,
+};
 use async_trait::async_trait;
-use clap::{Parser, Subcommand};
-use rand::SeedableRng;
+use clap::{ArgEnum, Parser, Subcommand};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use sha2::{
+    digest::{consts::U64, generic_array::GenericArray, FixedOutput, Reset, Update},
+    Digest, Sha256,
+};
 use std::{
     collections::{HashMap, HashSet},
+    convert::{TryFrom, TryInto},
+    ffi::OsStr,
+    io::{Read, Write},
     path::{Path, PathBuf},
+    str::FromStr,
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
+use zeroize::Zeroizing;
 
 pub const PUBLIC_KEY_EXTENSION: &str = "pub";
+pub const SIGNATURE_EXTENSION: &str = "sig";
+pub const MULTI_ED25519_PUBLIC_KEY_EXTENSION: &str = "multi.pub";
+pub const METADATA_EXTENSION: &str = "meta.json";
+pub const IDENTITY_EXTENSION: &str = "identity.yaml";
+/// Extension of the identity blob file a `--network-identity` file refers to
+pub const IDENTITY_BLOB_EXTENSION: &str = "identity-blob.yaml";
+/// Extension of the file beside a private key file holding the address of its on-chain account
+pub const ADDRESS_EXTENSION: &str = "address";
+
+/// Length of a private key of any key type, as imported with `generate --from-private-hex`
+const IMPORTED_PRIVATE_KEY_LENGTH: usize = 32;
+
+/// Most keys a multi-ed25519 key can have
+const MAX_MULTI_ED25519_KEYS: usize = 32;
+
+/// Something done with a key, generic over the type the key is loaded as
+///
+/// Commands take the key type as an argument, so [`visit_key_type`] picks the type of the key
+/// once for all of them.
+trait KeyTypeVisitor {
+    type Output;
+
+    /// Does it with a private key of type `Key`
+    fn visit_private_key<Key>(&self) -> CliTypedResult<Self::Output>
+    where
+        Key: PrivateKey + ValidCryptoMaterial + KeyFormat,
+        Key::PublicKeyMaterial: ValidCryptoMaterial + KeyFormat;
+
+    /// Does it with a public key of type `Key`
+    fn visit_public_key<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+    ) -> CliTypedResult<Self::Output>;
+}
+
+/// Visits the private or public key type of `key_type`
+fn visit_key_type<Visitor: KeyTypeVisitor>(
+    visitor: &Visitor,
+    key_type: KeyType,
+    is_public_key: bool,
+) -> CliTypedResult<Visitor::Output> {
+    match (key_type, is_public_key) {
+        (KeyType::X25519, false) => visitor.visit_private_key::<x25519::PrivateKey>(),
+        (KeyType::X25519, true) => visitor.visit_public_key::<x25519::PublicKey>(),
+        (KeyType::Ed25519, false) => visitor.visit_private_key::<ed25519::Ed25519PrivateKey>(),
+        (KeyType::Ed25519, true) => visitor.visit_public_key::<ed25519::Ed25519PublicKey>(),
+        (KeyType::Secp256k1, false) => {
+            visitor.visit_private_key::<secp256k1::Secp256k1PrivateKey>()
+        }
+        (KeyType::Secp256k1, true) => visitor.visit_public_key::<secp256k1::Secp256k1PublicKey>(),
+        (KeyType::Secp256r1, false) => {
+            visitor.visit_private_key::<secp256r1::Secp256r1PrivateKey>()
+        }
+        (KeyType::Secp256r1, true) => visitor.visit_public_key::<secp256r1::Secp256r1PublicKey>(),
+    }
+}
+
+/// Canonical bytes of a loaded key, and of its public key
+struct KeyBytes {
+    key: Zeroizing<Vec<u8>>,
+    public_key: Vec<u8>,
+}
+
+/// Loads a key file, checking its checksum unless skipped, as a [`KeyTypeVisitor`]
+struct LoadKeyBytes<'a> {
+    checksum_options: &'a ChecksumOptions,
+    key_file: &'a KeyFile,
+    name: &'static str,
+}
+
+impl KeyTypeVisitor for LoadKeyBytes<'_> {
+    type Output = KeyBytes;
+
+    fn visit_private_key<Key>(&self) -> CliTypedResult<KeyBytes>
+    where
+        Key: PrivateKey + ValidCryptoMaterial + KeyFormat,
+        Key::PublicKeyMaterial: ValidCryptoMaterial + KeyFormat,
+    {
+        let key: Key = self.checksum_options.load(self.key_file, self.name)?;
+        Ok(KeyBytes {
+            key: Zeroizing::new(key.to_bytes()),
+            public_key: key.public_key().to_bytes(),
+        })
+    }
+
+    fn visit_public_key<Key: ValidCryptoMaterial + KeyFormat>(&self) -> CliTypedResult<KeyBytes> {
+        let key: Key = self.checksum_options.load(self.key_file, self.name)?;
+        Ok(KeyBytes {
+            key: Zeroizing::new(key.to_bytes()),
+            public_key: key.to_bytes(),
+        })
+    }
+}
 
 /// CLI tool for generating, inspecting, and interacting with keys.
 #[derive(Debug, Subcommand)]
 pub enum KeyTool {
     Generate(GenerateKey),
     ExtractPeer(ExtractPeer),
+    ExtractPeerId(ExtractPeerId),
+    ExtractX25519PublicKey(ExtractX25519PublicKey),
+    Sign(Sign),
+    Verify(Verify),
+    Inspect(Inspect),
+    Convert(Convert),
+    AuthKey(AuthKey),
+    PublicKey(DerivePublicKey),
+    RotateProposal(RotateProposal),
+    Fingerprint(Fingerprint),
+    Diff(DiffKeys),
+    ExportSsh(ExportSsh),
+    ExportJwk(ExportJwk),
+    Export(ExportKey),
+    ValidateAddress(ValidateAddress),
+    Encrypt(EncryptKey),
+    Decrypt(DecryptKey),
+    List(ListKeys),
+    Recover(RecoverKey),
+    Check(CheckKey),
+    #[clap(alias = "verify-manifest")]
+    BatchVerify(BatchVerify),
+    Derive(DeriveKey),
 }
 
 impl KeyTool {
     pub async fn execute(self) -> CliResult {
         match self {
+            // Printed keys are the only output, so they can be piped
+            KeyTool::Generate(tool) if tool.save_params.prints_keys() || tool.quiet => {
+                tool.execute_serialized_without_output().await
+            }
             KeyTool::Generate(tool) => tool.execute_serialized().await,
             KeyTool::ExtractPeer(tool) => tool.execute_serialized().await,
+            KeyTool::ExtractPeerId(tool) => tool.execute_serialized().await,
+            KeyTool::ExtractX25519PublicKey(tool) => tool.execute_serialized().await,
+            KeyTool::Sign(tool) => tool.execute_serialized().await,
+            KeyTool::Verify(tool) => tool.execute_serialized_success().await,
+            KeyTool::Inspect(tool) => tool.execute_serialized().await,
+            KeyTool::Convert(tool) if tool.io_options.is_stdout() => {
+                tool.execute_serialized_without_output().await
+            }
+            KeyTool::Convert(tool) => tool.execute_serialized().await,
+            KeyTool::AuthKey(tool) => tool.execute_serialized().await,
+            KeyTool::PublicKey(tool) if tool.writes_to_stdout() => {
+                tool.execute_serialized_without_output().await
+            }
+            KeyTool::PublicKey(tool) => tool.execute_serialized().await,
+            KeyTool::RotateProposal(tool) => tool.execute_serialized().await,
+            KeyTool::Fingerprint(tool) => tool.execute_serialized().await,
+            KeyTool::Diff(tool) => tool.execute_serialized_success().await,
+            KeyTool::ExportSsh(tool) => tool.execute_serialized().await,
+            KeyTool::ExportJwk(tool) => tool.execute_serialized().await,
+            KeyTool::Export(tool) if tool.io_options.is_stdout() => {
+                tool.execute_serialized_without_output().await
+            }
+            KeyTool::Export(tool) => tool.execute_serialized().await,
+            KeyTool::ValidateAddress(tool) => tool.execute_serialized().await,
+            KeyTool::Encrypt(tool) => tool.execute_serialized().await,
+            KeyTool::Decrypt(tool) => tool.execute_serialized().await,
+            KeyTool::List(tool) => tool.execute_serialized().await,
+            KeyTool::Recover(tool) => tool.execute_serialized().await,
+            KeyTool::Check(tool) => tool.execute_serialized().await,
+            KeyTool::BatchVerify(tool) => tool.execute_serialized().await,
+            KeyTool::Derive(tool) => tool.execute_serialized().await,
         }
     }
 }
@@ -68,10 +256,9 @@ impl CliCommand<HashMap<AccountAddress, Peer>> for ExtractPeer {
         self.output_file_options.check_file()?;
 
         // Load key based on public or private
-        let public_key = self.private_key_input_options.extract_x25519_public_key(
-            self.encoding_options.encoding,
-            &self.profile_options.profile,
-        )?;
+        let public_key = self
+            .private_key_input_options
+            .extract_x25519_public_key(&self.encoding_options, &self.profile_options.profile)?;
 
         // Build peer info
         let peer_id = from_identity_public_key(public_key);
@@ -92,150 +279,5054 @@ impl CliCommand<HashMap<AccountAddress, Peer>> for ExtractPeer {
     }
 }
 
-/// Generates a `x25519` or `ed25519` key.
+/// CLI tool for deriving the peer id of a `x25519` private key
 ///
-/// This can be used for generating an identity.  Two files will be created
-/// `output_file` and `output_file.pub`.  `output_file` will contain the private
-/// key encoded with the `encoding` and `output_file.pub` will contain the public
-/// key encoded with the `encoding`.
+/// The peer id is derived from the public key of the `x25519` key in `key_file`, and is
+/// printed in hex.
 #[derive(Debug, Parser)]
-pub struct GenerateKey {
-    /// Key type: `x25519` or `ed25519`
-    #[clap(long, default_value = "ed25519")]
-    key_type: KeyType,
+pub struct ExtractPeerId {
+    /// `x25519` private key input file name, or `-` to read from stdin
+    #[clap(long, parse(from_os_str))]
+    key_file: PathBuf,
     #[clap(flatten)]
-    save_params: SaveKey,
+    encoding_options: EncodingOptions,
 }
 
 #[async_trait]
-impl CliCommand<HashMap<&'static str, PathBuf>> for GenerateKey {
+impl CliCommand<AccountAddress> for ExtractPeerId {
     fn command_name(&self) -> &'static str {
-        "GenerateKey"
+        "ExtractPeerId"
     }
 
-    async fn execute(self) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
-        self.save_params.check_key_file()?;
+    async fn execute(self) -> CliTypedResult<AccountAddress> {
+        let private_key =
+            load_x25519_private_key(self.encoding_options.encoding, self.key_file.as_path())?;
+        Ok(from_identity_public_key(private_key.public_key()))
+    }
+}
 
-        // Generate a ed25519 key
-        let ed25519_key = Self::generate_ed25519_in_memory();
+/// Loads a `x25519` private key, rejecting keys that aren't already clamped
+///
+/// Any 32 bytes will load as a `x25519` key, but every `x25519` key generated by this tool is
+/// clamped.  An `ed25519` key will almost never be, so a key that changes when loaded is most
+/// likely the wrong key type.
+pub fn load_x25519_private_key(
+    encoding: EncodingType,
+    key_file: &Path,
+) -> CliTypedResult<x25519::PrivateKey> {
+    let data = Zeroizing::new(read_from_file_or_stdin(key_file)?);
+    let private_key: x25519::PrivateKey = encoding.decode_key("--key-file", data.to_vec())?;
+    let raw_key: ed25519::Ed25519PrivateKey = encoding.decode_key("--key-file", data.to_vec())?;
+    if !constant_time_eq(&private_key.to_bytes(), &raw_key.to_bytes()) {
+        return Err(CliError::UnexpectedError(format!(
+            "Key in {} is not a x25519 private key, is it an ed25519 key?",
+            key_file.display()
+        )));
+    }
+    Ok(private_key)
+}
 
-        // Convert it to the appropriate type and save it
-        match self.key_type {
-            KeyType::X25519 => {
-                let private_key =
-                    x25519::PrivateKey::from_ed25519_private_bytes(&ed25519_key.to_bytes())
-                        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
-                self.save_params.save_key(&private_key, "x25519")
+/// CLI tool for converting an `ed25519` public key to a `x25519` public key
+///
+/// This is for computing a peer identity when only the public half of an `ed25519` key is
+/// known.  The `x25519` public key is printed in hex.
+#[derive(Debug, Parser)]
+pub struct ExtractX25519PublicKey {
+    /// `ed25519` public key input file name, or `-` to read from stdin
+    #[clap(long, parse(from_os_str))]
+    key_file: PathBuf,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    checksum_options: ChecksumOptions,
+}
+
+#[async_trait]
+impl CliCommand<x25519::PublicKey> for ExtractX25519PublicKey {
+    fn command_name(&self) -> &'static str {
+        "ExtractX25519PublicKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<x25519::PublicKey> {
+        let key_file = self.encoding_options.key_file(&self.key_file);
+        let public_key: ed25519::Ed25519PublicKey =
+            self.checksum_options.load(&key_file, "--key-file")?;
+        ed25519_to_x25519_public_key(&public_key)
+    }
+}
+
+/// Length of the seed read by `--rng-from-file`
+pub const RNG_SEED_LENGTH: usize = 32;
+
+/// Reads the seed of `--rng-from-file`, which must be exactly [`RNG_SEED_LENGTH`] bytes
+///
+/// At most one byte more is read, so the file can also be a device that never ends.
+pub fn read_rng_seed_file(path: &Path) -> CliTypedResult<Zeroizing<[u8; RNG_SEED_LENGTH]>> {
+    let name = path.display().to_string();
+    let file = std::fs::File::open(path)
+        .map_err(|err| CliError::UnableToReadFile(name.clone(), err.to_string()))?;
+    let mut bytes = Zeroizing::new(Vec::with_capacity(RNG_SEED_LENGTH + 1));
+    file.take(RNG_SEED_LENGTH as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|err| CliError::IO(name.clone(), err))?;
+    if bytes.len() != RNG_SEED_LENGTH {
+        return Err(CliError::CommandArgumentError(format!(
+            "--rng-from-file {} must be exactly {} bytes, got {}",
+            name,
+            RNG_SEED_LENGTH,
+            if bytes.len() > RNG_SEED_LENGTH {
+                "more".to_string()
+            } else {
+                format!("{} bytes", bytes.len())
             }
-            KeyType::Ed25519 => self.save_params.save_key(&ed25519_key, "ed25519"),
+        )));
+    }
+    let mut seed = Zeroizing::new([0u8; RNG_SEED_LENGTH]);
+    seed.copy_from_slice(&bytes);
+    Ok(seed)
+}
+
+/// Converts an `ed25519` public key to the `x25519` public key of the same point
+///
+/// Small order points are rejected, as they'd give a `x25519` key with no security.
+pub fn ed25519_to_x25519_public_key(
+    public_key: &ed25519::Ed25519PublicKey,
+) -> CliTypedResult<x25519::PublicKey> {
+    let bytes = public_key.to_bytes();
+    let point = CompressedEdwardsY::from_slice(&bytes)
+        .decompress()
+        .ok_or_else(|| {
+            CliError::UnexpectedError("ed25519 public key isn't a valid point".to_string())
+        })?;
+    if point.is_small_order() {
+        return Err(CliError::UnexpectedError(
+            "ed25519 public key is a small order point".to_string(),
+        ));
+    }
+    x25519::PublicKey::from_ed25519_public_bytes(&bytes).map_err(|err| {
+        CliError::UnexpectedError(format!("Failed to convert ed25519 to x25519 {:?}", err))
+    })
+}
+
+/// Name of the public key file of a private key file e.g. `mykey.pub` for `mykey.key`, or
+/// `mykey.pem.pub` for `mykey.pem`
+pub fn public_key_file_of(private_key_file: &Path) -> CliTypedResult<PathBuf> {
+    public_key_file_with_extension(private_key_file, PUBLIC_KEY_EXTENSION)
+}
+
+/// Name of the public key file of a private key file like `public_key_file_of`, with
+/// `extension` in place of `pub`, e.g. `mykey.ed25519.pub` for `mykey.key` and `ed25519.pub`
+pub fn public_key_file_with_extension(
+    private_key_file: &Path,
+    extension: &str,
+) -> CliTypedResult<PathBuf> {
+    replace_file_extension(private_key_file, PRIVATE_KEY_EXTENSIONS, extension)
+}
+
+/// Parses a `--public-key-extension`, which starts with a dot and names no directory
+pub fn parse_public_key_extension(str: &str) -> CliTypedResult<String> {
+    match str.strip_prefix('.') {
+        Some(extension)
+            if !extension.is_empty()
+                && !extension.starts_with('.')
+                && !extension.contains(std::path::is_separator) =>
+        {
+            Ok(str.to_string())
         }
+        _ => Err(CliError::CommandArgumentError(format!(
+            "Invalid public key extension '{}', expected a dot and an extension, e.g. '.public'",
+            str
+        ))),
     }
 }
 
-impl GenerateKey {
-    /// A test friendly typed key generation for x25519 keys.
-    pub async fn generate_x25519(
-        encoding: EncodingType,
-        key_file: &Path,
-    ) -> CliTypedResult<(x25519::PrivateKey, x25519::PublicKey)> {
-        let args = format!(
-            "generate --key-type {key_type:?} --output-file {key_file} --encoding {encoding:?} --assume-yes",
-            key_type = KeyType::X25519,
-            key_file = key_file.to_str().unwrap(),
-            encoding = encoding,
-        );
-        let command = GenerateKey::parse_from(args.split_whitespace());
-        command.execute().await?;
-        Ok((
-            encoding.load_key("private_key", key_file)?,
-            encoding.load_key(
-                "public_key",
-                &append_file_extension(key_file, PUBLIC_KEY_EXTENSION)?,
-            )?,
-        ))
+/// Checks a `x25519` key converted from an `ed25519` key, before it's saved
+///
+/// The clamped `x25519` key must have the public key of the same point as the `ed25519` key,
+/// and must still have it after a round trip through `encoding`.  Otherwise a key that fails
+/// Noise handshakes would be saved without any sign that it's wrong.
+pub fn verify_x25519_conversion(
+    ed25519_key: &ed25519::Ed25519PrivateKey,
+    x25519_key: &x25519::PrivateKey,
+    encoding: EncodingType,
+) -> CliTypedResult<()> {
+    let expected_public_key = ed25519_to_x25519_public_key(&ed25519_key.public_key())?;
+    if x25519_key.public_key() != expected_public_key {
+        return Err(CliError::UnexpectedError(
+            "Converted x25519 key doesn't have the public key of its ed25519 key".to_string(),
+        ));
     }
 
-    /// A test friendly typed key generation for e25519 keys.
-    pub async fn generate_ed25519(
-        encoding: EncodingType,
-        key_file: &Path,
-    ) -> CliTypedResult<(ed25519::Ed25519PrivateKey, ed25519::Ed25519PublicKey)> {
-        let args = format!(
-            "generate --key-type {key_type:?} --output-file {key_file} --encoding {encoding:?} --assume-yes",
-            key_type = KeyType::Ed25519,
-            key_file = key_file.to_str().unwrap(),
-            encoding = encoding,
+    let encoded_key = encoding.encode_key("x25519 key", x25519_key)?;
+    let decoded_key: x25519::PrivateKey =
+        encoding.decode_key("x25519 key", encoded_key.to_vec())?;
+    if decoded_key.public_key() != expected_public_key {
+        return Err(CliError::UnexpectedError(format!(
+            "Converted x25519 key doesn't have the same public key after being {} encoded",
+            encoding
+        )));
+    }
+    Ok(())
+}
+
+/// Signs a message with an `ed25519` private key
+///
+/// The message can be given hex encoded on the command line with `message`, or as the raw
+/// bytes of a `message-file`.  The signature is printed encoded with the `encoding`, and if an
+/// `output-file` is given, it's saved to `output_file.sig`.
+///
+/// With `prehashed`, the message is the SHA-512 digest of the message, and is signed with
+/// Ed25519ph instead.  With `multi`, the message is signed by each of the `signer-key-file`s
+/// instead, giving a multi-ed25519 signature.
+#[derive(Debug, Parser)]
+pub struct Sign {
+    #[clap(flatten)]
+    private_key_input_options: PrivateKeyInputOptions,
+    #[clap(flatten)]
+    message_input_options: MessageInputOptions,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    profile_options: ProfileOptions,
+    /// Output file name, the signature will be saved to `output_file.sig`
+    #[clap(long, parse(from_os_str))]
+    output_file: Option<PathBuf>,
+    /// Multi-ed25519 public key file of the signers, e.g. `output_file.multi.pub` of
+    /// `generate --multi`, to sign with the `--signer-key-file`s
+    #[clap(
+        long,
+        parse(from_os_str),
+        requires_all = &["signer_key_file", "signers"],
+        conflicts_with_all = &["private_key_input", "prehashed"]
+    )]
+    multi: Option<PathBuf>,
+    /// Private key file of a signer of a `--multi` signature, once for each signer
+    #[clap(
+        long,
+        parse(from_os_str),
+        multiple_occurrences = true,
+        requires = "multi"
+    )]
+    signer_key_file: Vec<PathBuf>,
+    /// Indices of the signers in the `--multi` public key, e.g. `0,2`, one for each
+    /// `--signer-key-file` in the same order
+    #[clap(long, use_value_delimiter = true, requires = "multi")]
+    signers: Vec<u8>,
+    #[clap(flatten)]
+    audit_log_options: AuditLogOptions,
+}
+
+#[async_trait]
+impl CliCommand<String> for Sign {
+    fn command_name(&self) -> &'static str {
+        "Sign"
+    }
+
+    async fn execute(self) -> CliTypedResult<String> {
+        let mut public_key = None;
+        let result = self.sign(&mut public_key);
+        self.audit_log_options.record(
+            "sign",
+            Some(KeyType::Ed25519.name()),
+            public_key,
+            None,
+            result.as_ref().err(),
         );
-        let command = GenerateKey::parse_from(args.split_whitespace());
-        command.execute().await?;
-        Ok((
-            encoding.load_key("private_key", key_file)?,
-            encoding.load_key(
-                "public_key",
-                &append_file_extension(key_file, PUBLIC_KEY_EXTENSION)?,
-            )?,
-        ))
+        result
     }
+}
 
-    /// Generates an `Ed25519PrivateKey` without saving it to disk
-    pub fn generate_ed25519_in_memory() -> ed25519::Ed25519PrivateKey {
-        let mut rng = rand::rngs::StdRng::from_entropy();
-        ed25519::Ed25519PrivateKey::generate(&mut rng)
+impl Sign {
+    /// Signs the message, and keeps the public key of the private key for the audit log
+    fn sign(&self, public_key: &mut Option<String>) -> CliTypedResult<String> {
+        let encoding = self.encoding_options.encoding;
+        let encoded_signature = if let Some(ref multi) = self.multi {
+            let multi_public_key: MultiEd25519PublicKey =
+                self.encoding_options.key_file(multi).load("--multi")?;
+            *public_key = Some(hex::encode(multi_public_key.to_bytes()));
+            let message = self.message_input_options.message()?;
+            let signature = self.sign_multi(&multi_public_key, &message)?;
+            encoding.encode_key("signature", &signature)?
+        } else {
+            let private_key = self
+                .private_key_input_options
+                .extract_private_key(&self.encoding_options, &self.profile_options.profile)?;
+            *public_key = Some(hex::encode(private_key.public_key().to_bytes()));
+            let message = self.message_input_options.message()?;
+
+            let signature = if self.message_input_options.prehashed {
+                sign_prehashed(&private_key, &message)?
+            } else {
+                sign_message(&private_key, &message)?
+            };
+            encoding.encode_key("signature", &signature)?
+        };
+
+        if let Some(ref output_file) = self.output_file {
+            let signature_file = append_file_extension(output_file, SIGNATURE_EXTENSION)?;
+            write_to_file(&signature_file, "signature", &encoded_signature)?;
+        }
+
+        // BCS isn't printable, so show the BCS bytes as hex instead
+        match encoding {
+            EncodingType::BCS | EncodingType::Der | EncodingType::Raw => {
+                Ok(hex::encode_upper(encoded_signature.as_slice()))
+            }
+            _ => Ok(String::from_utf8(encoded_signature.to_vec())?),
+        }
     }
 
-    pub fn generate_x25519_in_memory() -> CliTypedResult<x25519::PrivateKey> {
-        let key = Self::generate_ed25519_in_memory();
-        x25519::PrivateKey::from_ed25519_private_bytes(&key.to_bytes()).map_err(|err| {
-            CliError::UnexpectedError(format!("Failed to convert ed25519 to x25519 {:?}", err))
-        })
+    /// Signs the message with each `--signer-key-file`, as the signer of `multi_public_key` at
+    /// its index in `--signers`
+    ///
+    /// Each key must be the public key at its index, and there must be enough signers to meet
+    /// the threshold, so a signature that can never verify isn't made.
+    fn sign_multi(
+        &self,
+        multi_public_key: &MultiEd25519PublicKey,
+        message: &[u8],
+    ) -> CliTypedResult<MultiEd25519Signature> {
+        if self.signer_key_file.len() != self.signers.len() {
+            return Err(CliError::CommandArgumentError(format!(
+                "--signers must give an index for each of the {} --signer-key-files, got {}",
+                self.signer_key_file.len(),
+                self.signers.len()
+            )));
+        }
+        let threshold = *multi_public_key.threshold();
+        if self.signers.len() < threshold as usize {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} signers can't meet the threshold of {} of the --multi public key",
+                self.signers.len(),
+                threshold
+            )));
+        }
+
+        let public_keys = multi_public_key.public_keys();
+        let mut signatures = Vec::with_capacity(self.signers.len());
+        for (key_file, index) in self.signer_key_file.iter().zip(&self.signers) {
+            let expected_public_key = public_keys.get(*index as usize).ok_or_else(|| {
+                CliError::CommandArgumentError(format!(
+                    "Signer {} is out of range, the --multi public key has {} signers",
+                    index,
+                    public_keys.len()
+                ))
+            })?;
+            let private_key: ed25519::Ed25519PrivateKey = self
+                .encoding_options
+                .key_file(key_file)
+                .with_passphrase_options(
+                    self.private_key_input_options.passphrase_options().clone(),
+                )
+                .load("--signer-key-file")?;
+            if &private_key.public_key() != expected_public_key {
+                return Err(CliError::CommandArgumentError(format!(
+                    "{} isn't the key of signer {} of the --multi public key",
+                    key_file.display(),
+                    index
+                )));
+            }
+            signatures.push((sign_message(&private_key, message)?, *index));
+        }
+        MultiEd25519Signature::new(signatures)
+            .map_err(|err| CliError::CommandArgumentError(format!("Invalid --signers: {}", err)))
     }
 }
 
+/// Signs the raw bytes of `message` with an `ed25519` private key
+///
+/// `SigningKey::sign` only signs `CryptoHash` types, which prepends a domain separator to the
+/// message.  This signs the bytes as given, so the signature can be checked by any `ed25519`
+/// implementation.
+pub fn sign_message(
+    private_key: &ed25519::Ed25519PrivateKey,
+    message: &[u8],
+) -> CliTypedResult<ed25519::Ed25519Signature> {
+    let secret_key = ed25519_dalek::SecretKey::from_bytes(&private_key.to_bytes())
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+    let public_key = ed25519_dalek::PublicKey::from(&secret_key);
+    let signature = ed25519_dalek::ExpandedSecretKey::from(&secret_key).sign(message, &public_key);
+    ed25519::Ed25519Signature::try_from(signature.to_bytes().as_ref())
+        .map_err(|err| CliError::UnexpectedError(format!("Failed to create signature {:?}", err)))
+}
+
+/// Verifies an `ed25519` signature of a message
+///
+/// The message is given the same way as with `sign`, and the `signature-file` must be encoded
+/// with the `encoding`.  Fails if the signature doesn't match.  A signature made with
+/// `prehashed` must be verified with `prehashed` too.
 #[derive(Debug, Parser)]
-pub struct SaveKey {
+pub struct Verify {
     #[clap(flatten)]
-    file_options: SaveFile,
+    public_key_input_options: PublicKeyInputOptions,
+    #[clap(flatten)]
+    message_input_options: MessageInputOptions,
+    /// Signature input file name
+    #[clap(long, parse(from_os_str))]
+    signature_file: PathBuf,
     #[clap(flatten)]
     encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    profile_options: ProfileOptions,
 }
 
-impl SaveKey {
-    /// Public key file name
-    fn public_key_file(&self) -> CliTypedResult<PathBuf> {
-        append_file_extension(
-            self.file_options.output_file.as_path(),
-            PUBLIC_KEY_EXTENSION,
-        )
+#[async_trait]
+impl CliCommand<()> for Verify {
+    fn command_name(&self) -> &'static str {
+        "Verify"
     }
 
-    /// Check if the key file exists already
-    pub fn check_key_file(&self) -> CliTypedResult<()> {
-        // Check if file already exists
-        self.file_options.check_file()?;
-        check_if_file_exists(&self.public_key_file()?, self.file_options.prompt_options)
+    async fn execute(self) -> CliTypedResult<()> {
+        let encoding = self.encoding_options.encoding;
+        let public_key = self
+            .public_key_input_options
+            .extract_public_key(&self.encoding_options, &self.profile_options.profile)?;
+        let message = self.message_input_options.message()?;
+        let signature = load_signature(encoding, self.signature_file.as_path())?;
+        if self.message_input_options.prehashed {
+            verify_prehashed_signature(&public_key, &message, &signature)
+        } else {
+            verify_signature(&public_key, &message, &signature)
+        }
+    }
+}
+
+/// Verifies an `ed25519` signature of the raw bytes of `message`
+pub fn verify_signature(
+    public_key: &ed25519::Ed25519PublicKey,
+    message: &[u8],
+    signature: &ed25519::Ed25519Signature,
+) -> CliTypedResult<()> {
+    signature
+        .verify_arbitrary_msg(message, public_key)
+        .map_err(|_| CliError::UnexpectedError("signature verification failed".to_string()))
+}
+
+/// Length of the SHA-512 digest signed with Ed25519ph
+pub const PREHASHED_DIGEST_LENGTH: usize = 64;
+
+/// A SHA-512 digest computed elsewhere, which `ed25519-dalek` takes as the hash of the message
+/// it signs with Ed25519ph
+///
+/// `ed25519-dalek` only takes the digest as a hasher to finalize, so this finalizes to the
+/// bytes it's given.  They must be [`PREHASHED_DIGEST_LENGTH`] bytes long.
+#[derive(Clone, Default)]
+struct PrecomputedDigest(Vec<u8>);
+
+impl Update for PrecomputedDigest {
+    fn update(&mut self, data: impl AsRef<[u8]>) {
+        self.0.extend_from_slice(data.as_ref());
     }
+}
 
-    /// Saves a key to a file encoded in a string
-    pub fn save_key<Key: PrivateKey + ValidCryptoMaterial>(
-        &self,
-        key: &Key,
-        key_name: &'static str,
-    ) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
-        let encoded_private_key = self.encoding_options.encoding.encode_key(key_name, key)?;
-        let encoded_public_key = self
-            .encoding_options
-            .encoding
-            .encode_key(key_name, &key.public_key())?;
+impl FixedOutput for PrecomputedDigest {
+    type OutputSize = U64;
 
-        // Write private and public keys to files
-        let public_key_file = self.public_key_file()?;
-        self.file_options
-            .save_to_file_confidential(key_name, &encoded_private_key)?;
-        write_to_file(&public_key_file, key_name, &encoded_public_key)?;
+    fn finalize_into(self, out: &mut GenericArray<u8, U64>) {
+        out.copy_from_slice(&self.0);
+    }
 
-        let mut map = HashMap::new();
-        map.insert("PrivateKey Path", self.file_options.output_file.clone());
-        map.insert("PublicKey Path", public_key_file);
-        Ok(map)
+    fn finalize_into_reset(&mut self, out: &mut GenericArray<u8, U64>) {
+        out.copy_from_slice(&self.0);
+        self.reset();
+    }
+}
+
+impl Reset for PrecomputedDigest {
+    fn reset(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Checks a prehashed message is a whole SHA-512 digest, and wraps it for `ed25519-dalek`
+fn precomputed_digest(digest: &[u8]) -> CliTypedResult<PrecomputedDigest> {
+    if digest.len() != PREHASHED_DIGEST_LENGTH {
+        return Err(CliError::CommandArgumentError(format!(
+            "Prehashed message must be a {} byte SHA-512 digest, found {} bytes",
+            PREHASHED_DIGEST_LENGTH,
+            digest.len()
+        )));
+    }
+    Ok(PrecomputedDigest(digest.to_vec()))
+}
+
+/// Signs the SHA-512 `digest` of a message with an `ed25519` private key, using Ed25519ph
+///
+/// Like [`sign_message`], this signs without a domain separator, and with no context.
+pub fn sign_prehashed(
+    private_key: &ed25519::Ed25519PrivateKey,
+    digest: &[u8],
+) -> CliTypedResult<ed25519::Ed25519Signature> {
+    let digest = precomputed_digest(digest)?;
+    let secret_key = ed25519_dalek::SecretKey::from_bytes(&private_key.to_bytes())
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+    let public_key = ed25519_dalek::PublicKey::from(&secret_key);
+    let signature = ed25519_dalek::ExpandedSecretKey::from(&secret_key)
+        .sign_prehashed(digest, &public_key, None)
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+    ed25519::Ed25519Signature::try_from(signature.to_bytes().as_ref())
+        .map_err(|err| CliError::UnexpectedError(format!("Failed to create signature {:?}", err)))
+}
+
+/// Verifies an Ed25519ph signature of the SHA-512 `digest` of a message
+pub fn verify_prehashed_signature(
+    public_key: &ed25519::Ed25519PublicKey,
+    digest: &[u8],
+    signature: &ed25519::Ed25519Signature,
+) -> CliTypedResult<()> {
+    let digest = precomputed_digest(digest)?;
+    let public_key = ed25519_dalek::PublicKey::from_bytes(&public_key.to_bytes())
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+    let signature = ed25519_dalek::Signature::try_from(signature.to_bytes().as_ref())
+        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+    public_key
+        .verify_prehashed(digest, None, &signature)
+        .map_err(|_| CliError::UnexpectedError("signature verification failed".to_string()))
+}
+
+/// Verifies many `ed25519` signatures listed in a manifest
+///
+/// The manifest is either a JSON array of objects with `public_key`, `message`, and
+/// `signature`, or CSV records of `public_key,message,signature`, each in hex and optionally
+/// quoted.  Blank CSV lines, lines starting with `#`, and a `public_key,message,signature`
+/// header are skipped.  Entries that can't be parsed are reported without stopping, and fail
+/// the manifest like a bad signature.  The summary of every entry is output either way, and a
+/// failed manifest exits with code 1.
+///
+/// Each signature is verified on its own with the verification of `verify`, rather than with
+/// `ed25519` batch verification, which only tells whether every signature passed and not which
+/// ones failed.
+#[derive(Debug, Parser)]
+pub struct BatchVerify {
+    /// Manifest of the signatures, JSON if it ends in `.json` or starts with `[`, CSV otherwise
+    #[clap(long, parse(from_os_str))]
+    manifest: PathBuf,
+}
+
+/// An entry of a `batch-verify` JSON manifest
+#[derive(Debug, Deserialize)]
+struct ManifestEntry {
+    public_key: String,
+    message: String,
+    signature: String,
+}
+
+/// Result of one entry of a `batch-verify` manifest
+#[derive(Debug, Serialize)]
+pub struct VerifiedEntry {
+    /// Line of a CSV entry, or index from 1 of a JSON entry
+    entry: usize,
+    /// `pass`, `fail`, or `malformed`
+    result: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Summary of `batch-verify`
+#[derive(Debug, Serialize)]
+pub struct BatchVerifySummary {
+    passed: usize,
+    failed: usize,
+    malformed: usize,
+    entries: Vec<VerifiedEntry>,
+}
+
+#[async_trait]
+impl CliCommand<BatchVerifySummary> for BatchVerify {
+    fn command_name(&self) -> &'static str {
+        "BatchVerify"
+    }
+
+    async fn execute(self) -> CliTypedResult<BatchVerifySummary> {
+        let manifest = String::from_utf8(read_from_file(&self.manifest)?)
+            .map_err(|err| CliError::UnableToParse("--manifest", err.to_string()))?;
+        let is_json = self.manifest.extension() == Some(OsStr::new("json"))
+            || manifest.trim_start().starts_with('[');
+        let entries = if is_json {
+            parse_json_manifest(&manifest)?
+        } else {
+            parse_csv_manifest(&manifest)
+        };
+
+        let mut summary = BatchVerifySummary {
+            passed: 0,
+            failed: 0,
+            malformed: 0,
+            entries: Vec::with_capacity(entries.len()),
+        };
+        for (entry, parsed) in entries {
+            let (result, error) = match parsed.and_then(|entry| verify_manifest_entry(&entry)) {
+                Ok(()) => ("pass", None),
+                Err(ManifestError::Malformed(err)) => ("malformed", Some(err)),
+                Err(ManifestError::Failed(err)) => ("fail", Some(err)),
+            };
+            match result {
+                "pass" => summary.passed += 1,
+                "fail" => summary.failed += 1,
+                _ => summary.malformed += 1,
+            }
+            summary.entries.push(VerifiedEntry {
+                entry,
+                result,
+                error,
+            });
+        }
+
+        if summary.failed + summary.malformed > 0 {
+            return Err(CliError::BatchVerifyFailed(
+                serde_json::to_value(&summary)
+                    .map_err(|err| CliError::UnexpectedError(err.to_string()))?,
+            ));
+        }
+        Ok(summary)
+    }
+}
+
+/// Why an entry of a manifest didn't pass
+enum ManifestError {
+    Malformed(String),
+    Failed(String),
+}
+
+/// Entries of a JSON manifest, by index from 1
+///
+/// Entries that aren't objects of strings are malformed, but JSON that isn't an array fails
+/// the whole manifest.
+fn parse_json_manifest(
+    manifest: &str,
+) -> CliTypedResult<Vec<(usize, Result<ManifestEntry, ManifestError>)>> {
+    let values: Vec<serde_json::Value> = serde_json::from_str(manifest)
+        .map_err(|err| CliError::UnableToParse("--manifest", err.to_string()))?;
+    Ok(values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let entry = serde_json::from_value(value)
+                .map_err(|err| ManifestError::Malformed(err.to_string()));
+            (index + 1, entry)
+        })
+        .collect())
+}
+
+/// Header of a CSV manifest, which is optional
+const CSV_MANIFEST_HEADER: [&str; 3] = ["public_key", "message", "signature"];
+
+/// Entries of a CSV manifest, by the line each record starts on
+fn parse_csv_manifest(manifest: &str) -> Vec<(usize, Result<ManifestEntry, ManifestError>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .comment(Some(b'#'))
+        .trim(csv::Trim::All)
+        .from_reader(manifest.as_bytes());
+    reader
+        .records()
+        .filter_map(|record| {
+            let line = match &record {
+                Ok(record) => record.position(),
+                Err(err) => err.position(),
+            }
+            .map_or(0, |position| position.line() as usize);
+            let entry = match record {
+                Ok(record) if is_csv_manifest_header(&record) => return None,
+                Ok(record) => match (record.get(0), record.get(1), record.get(2), record.len()) {
+                    (Some(public_key), Some(message), Some(signature), 3) => Ok(ManifestEntry {
+                        public_key: public_key.to_string(),
+                        message: message.to_string(),
+                        signature: signature.to_string(),
+                    }),
+                    _ => Err(ManifestError::Malformed(format!(
+                        "expected 3 fields, got {}",
+                        record.len()
+                    ))),
+                },
+                Err(err) => Err(ManifestError::Malformed(err.to_string())),
+            };
+            Some((line, entry))
+        })
+        .collect()
+}
+
+/// Whether a CSV record is the header of a manifest, in any case
+fn is_csv_manifest_header(record: &csv::StringRecord) -> bool {
+    record.len() == CSV_MANIFEST_HEADER.len()
+        && record
+            .iter()
+            .zip(CSV_MANIFEST_HEADER)
+            .all(|(field, name)| field.eq_ignore_ascii_case(name))
+}
+
+/// Verifies the signature of an entry with the single signature verification of `verify`
+fn verify_manifest_entry(entry: &ManifestEntry) -> Result<(), ManifestError> {
+    let decode = |name: &str, text: &str| {
+        hex::decode(text.strip_prefix("0x").unwrap_or(text))
+            .map_err(|err| ManifestError::Malformed(format!("invalid {}: {}", name, err)))
+    };
+    let public_key =
+        ed25519::Ed25519PublicKey::try_from(decode("public_key", &entry.public_key)?.as_slice())
+            .map_err(|err| ManifestError::Malformed(format!("invalid public_key: {}", err)))?;
+    let signature =
+        ed25519::Ed25519Signature::try_from(decode("signature", &entry.signature)?.as_slice())
+            .map_err(|err| ManifestError::Malformed(format!("invalid signature: {}", err)))?;
+    let message = decode("message", &entry.message)?;
+    verify_signature(&public_key, &message, &signature)
+        .map_err(|err| ManifestError::Failed(err.to_string()))
+}
+
+/// Loads an `ed25519` signature, checking the length first for a clearer error
+fn load_signature(
+    encoding: EncodingType,
+    signature_file: &Path,
+) -> CliTypedResult<ed25519::Ed25519Signature> {
+    let bytes = encoding.decode_bytes("--signature-file", read_from_file(signature_file)?)?;
+    if bytes.len() != ed25519::ED25519_SIGNATURE_LENGTH {
+        return Err(CliError::UnableToParse(
+            "--signature-file",
+            format!(
+                "expected {} bytes, got {}",
+                ed25519::ED25519_SIGNATURE_LENGTH,
+                bytes.len()
+            ),
+        ));
+    }
+    ed25519::Ed25519Signature::try_from(bytes.as_slice()).map_err(|err| {
+        CliError::UnableToParse("--signature-file", format!("Invalid signature {:?}", err))
+    })
+}
+
+/// Inspects a key file, showing what type of key it holds
+///
+/// Private keys are always 32 bytes, as are `ed25519` and `x25519` public keys, so 32 bytes are
+/// inspected as a private key unless using `--public-key`.  If they're also valid as a public
+/// key, that's reported rather than guessed, as they may be either.  Any 32 bytes are a private
+/// key of every key type, so with `--key-type auto` private keys are shown as `ed25519`, along
+/// with the other key types they may be.  Public keys are `ed25519` if they're a valid point,
+/// and `x25519` otherwise.
+#[derive(Debug, Parser)]
+pub struct Inspect {
+    /// Key input file name, or `-` to read from stdin
+    #[clap(long, parse(from_os_str))]
+    key_file: PathBuf,
+    /// Key type: `x25519`, `ed25519`, `secp256k1`, `secp256r1`, or `auto` to detect it
+    ///
+    /// `auto` tries `ed25519`, `x25519`, `secp256k1`, then `secp256r1`, and shows the first key
+    /// type that the key is valid as.  Any other key types it's also valid as are shown, with a
+    /// warning, as the key may be of one of them instead.
+    #[clap(long, default_value = "auto")]
+    key_type: KeyTypeSelection,
+    /// The key file holds a public key
+    #[clap(long)]
+    public_key: bool,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+}
+
+#[async_trait]
+impl CliCommand<KeyInfo> for Inspect {
+    fn command_name(&self) -> &'static str {
+        "Inspect"
+    }
+
+    async fn execute(self) -> CliTypedResult<KeyInfo> {
+        let bytes = self.encoding_options.encoding.decode_bytes_with_hrp(
+            "--key-file",
+            read_from_file_or_stdin(&self.key_file)?,
+            &self.encoding_options.bech32_hrp,
+        )?;
+        let is_private_key = bytes.len() == ed25519::ED25519_PRIVATE_KEY_LENGTH && !self.public_key;
+        let key_types = self.key_type.candidates();
+        let mut info = KeyInfo::detect(&bytes, is_private_key, key_types).ok_or_else(|| {
+            CliError::UnableToParse(
+                "--key-file",
+                format!(
+                    "{} bytes aren't a {} key of any key type, tried {}",
+                    bytes.len(),
+                    if is_private_key { "private" } else { "public" },
+                    key_types
+                        .iter()
+                        .map(KeyType::name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )
+        })?;
+        if !info.other_key_types.is_empty() {
+            eprintln!(
+                "WARNING: the key is valid as {}, but also as {}, use --key-type if it's one of them",
+                info.key_type,
+                info.other_key_types.join(", ")
+            );
+        }
+        if is_private_key && KeyInfo::detect(&bytes, false, key_types).is_some() {
+            info.may_be_public_key = true;
+            eprintln!(
+                "WARNING: the key is inspected as a private key, but it's also valid as a public \
+                key, use --public-key if it's one"
+            );
+        }
+        Ok(info)
+    }
+}
+
+/// Describes the bytes of a key as a [`KeyTypeVisitor`], failing if they aren't a valid key
+struct DescribeKeyBytes<'a> {
+    bytes: &'a [u8],
+    key_type: KeyType,
+}
+
+impl KeyTypeVisitor for DescribeKeyBytes<'_> {
+    type Output = KeyInfo;
+
+    fn visit_private_key<Key>(&self) -> CliTypedResult<KeyInfo>
+    where
+        Key: PrivateKey + ValidCryptoMaterial + KeyFormat,
+        Key::PublicKeyMaterial: ValidCryptoMaterial + KeyFormat,
+    {
+        let key = Key::try_from(self.bytes)
+            .map_err(|err| CliError::UnableToParse("key", err.to_string()))?;
+        Ok(KeyInfo::new(
+            self.key_type,
+            true,
+            self.bytes.len(),
+            key.public_key(),
+        ))
+    }
+
+    fn visit_public_key<Key: ValidCryptoMaterial + KeyFormat>(&self) -> CliTypedResult<KeyInfo> {
+        let key = Key::try_from(self.bytes)
+            .map_err(|err| CliError::UnableToParse("key", err.to_string()))?;
+        Ok(KeyInfo::new(self.key_type, false, self.bytes.len(), key))
+    }
+}
+
+/// Information about a key, as shown by `inspect`
+#[derive(Debug, Serialize)]
+pub struct KeyInfo {
+    /// Type of the key e.g. `ed25519`, the first of the key types tried that it's valid as
+    key_type: &'static str,
+    /// Other key types tried that the key is also valid as, so it may be of one of them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    other_key_types: Vec<&'static str>,
+    /// Whether the key is a private key or a public key
+    is_private_key: bool,
+    /// Whether the private key is also valid as a public key, so it may be a public key instead
+    may_be_public_key: bool,
+    /// Length of the key in bytes
+    length: usize,
+    /// Public key in hex, derived from the private key for private keys
+    public_key: String,
+    /// Authentication key in hex, only for `ed25519` keys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    authentication_key: Option<String>,
+}
+
+impl KeyInfo {
+    /// Describes the bytes of a private or public key as the first of `key_types` they're valid
+    /// as, listing the others they're also valid as
+    fn detect(bytes: &[u8], is_private_key: bool, key_types: &[KeyType]) -> Option<KeyInfo> {
+        let mut valid_key_types = key_types.iter().filter_map(|key_type| {
+            let visitor = DescribeKeyBytes {
+                bytes,
+                key_type: *key_type,
+            };
+            visit_key_type(&visitor, *key_type, !is_private_key).ok()
+        });
+        let mut info = valid_key_types.next()?;
+        info.other_key_types = valid_key_types.map(|other| other.key_type).collect();
+        Some(info)
+    }
+
+    fn new<Key: ValidCryptoMaterial>(
+        key_type: KeyType,
+        is_private_key: bool,
+        length: usize,
+        public_key: Key,
+    ) -> KeyInfo {
+        let public_key = public_key.to_bytes();
+        // Only ed25519 keys have an authentication key
+        let authentication_key = match key_type {
+            KeyType::Ed25519 => ed25519::Ed25519PublicKey::try_from(public_key.as_slice())
+                .ok()
+                .map(|public_key| hex::encode(AuthenticationKey::ed25519(&public_key).to_vec())),
+            _ => None,
+        };
+        KeyInfo {
+            key_type: key_type.name(),
+            other_key_types: Vec::new(),
+            is_private_key,
+            may_be_public_key: false,
+            length,
+            public_key: hex::encode(public_key),
+            authentication_key,
+        }
+    }
+}
+
+/// Lists the key pairs of a directory, from each public key file ending in `.pub`
+///
+/// The private key file of a pair is the public key file without `.pub`, or with `.key` or
+/// `.priv` in its place.  Files that can't be read or aren't a known key type are skipped, and
+/// listed separately.
+#[derive(Debug, Parser)]
+pub struct ListKeys {
+    /// Directory to list the keys of
+    #[clap(long, parse(from_os_str))]
+    dir: PathBuf,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+    /// Output format: `default` for a table, or `json` for an object describing each key
+    #[clap(long, default_value = "default")]
+    output_format: OutputFormat,
+}
+
+/// A key pair found by `list`
+#[derive(Debug, Serialize)]
+pub struct ListedKey {
+    public_key_file: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    private_key_file: Option<PathBuf>,
+    key_type: &'static str,
+    /// Public key in hex
+    public_key: String,
+    /// Account address, only for `ed25519` keys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account_address: Option<AccountAddress>,
+}
+
+/// A file skipped by `list`, and why
+#[derive(Debug, Serialize)]
+pub struct SkippedKeyFile {
+    file: PathBuf,
+    error: String,
+}
+
+/// Keys of a directory, as shown by `list --output-format json`
+#[derive(Debug, Serialize)]
+pub struct KeyList {
+    keys: Vec<ListedKey>,
+    skipped: Vec<SkippedKeyFile>,
+}
+
+/// Output of `list`, in the shape chosen by `--output-format`
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum ListKeysOutput {
+    /// Lines of a table of the keys, followed by the skipped files
+    Table(Vec<String>),
+    Json(KeyList),
+}
+
+/// Length of the public key prefix shown in the `list` table, in hex digits
+const LISTED_PUBLIC_KEY_PREFIX_LENGTH: usize = 16;
+
+#[async_trait]
+impl CliCommand<ListKeysOutput> for ListKeys {
+    fn command_name(&self) -> &'static str {
+        "ListKeys"
+    }
+
+    fn json_errors(&self) -> bool {
+        self.output_format == OutputFormat::Json
+    }
+
+    async fn execute(self) -> CliTypedResult<ListKeysOutput> {
+        let entries = std::fs::read_dir(&self.dir)
+            .map_err(|err| CliError::IO(self.dir.display().to_string(), err))?;
+        let mut public_key_files = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|err| CliError::IO(self.dir.display().to_string(), err))?
+                .path();
+            if path.is_file() && path.extension() == Some(OsStr::new(PUBLIC_KEY_EXTENSION)) {
+                public_key_files.push(path);
+            }
+        }
+        public_key_files.sort();
+
+        let mut key_list = KeyList {
+            keys: Vec::new(),
+            skipped: Vec::new(),
+        };
+        for public_key_file in public_key_files {
+            match self.list_key(&public_key_file) {
+                Ok(key) => key_list.keys.push(key),
+                Err(err) => key_list.skipped.push(SkippedKeyFile {
+                    file: public_key_file,
+                    error: err.to_string(),
+                }),
+            }
+        }
+
+        Ok(match self.output_format {
+            OutputFormat::Default => ListKeysOutput::Table(key_table(&key_list)),
+            OutputFormat::Json => ListKeysOutput::Json(key_list),
+        })
+    }
+}
+
+impl ListKeys {
+    /// Describes the key pair of a public key file
+    fn list_key(&self, public_key_file: &Path) -> CliTypedResult<ListedKey> {
+        let bytes = self.encoding_options.encoding.decode_bytes_with_hrp(
+            "public key",
+            read_from_file(public_key_file)?,
+            &self.encoding_options.bech32_hrp,
+        )?;
+        let info = KeyInfo::detect(&bytes, false, KeyType::ALL).ok_or_else(|| {
+            CliError::UnableToParse(
+                "public key",
+                format!("{} bytes aren't any known type of public key", bytes.len()),
+            )
+        })?;
+        let account_address = match ed25519::Ed25519PublicKey::try_from(bytes.as_slice()) {
+            Ok(public_key) if info.key_type == "ed25519" => {
+                Some(AuthenticationKey::ed25519(&public_key).derived_address())
+            }
+            _ => None,
+        };
+
+        // The private key file is named as `generate` would have named it
+        let stem = public_key_file.with_extension("");
+        let private_key_file = std::iter::once(stem.clone())
+            .chain(
+                PRIVATE_KEY_EXTENSIONS
+                    .iter()
+                    .map(|extension| stem.with_extension(extension)),
+            )
+            .find(|file| file.is_file());
+
+        Ok(ListedKey {
+            public_key_file: public_key_file.to_path_buf(),
+            private_key_file,
+            key_type: info.key_type,
+            public_key: info.public_key,
+            account_address,
+        })
+    }
+}
+
+/// Formats the keys of a list as a table, with the skipped files after it
+fn key_table(key_list: &KeyList) -> Vec<String> {
+    let file_name = |file: &Path| {
+        file.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+    let rows: Vec<[String; 5]> = key_list
+        .keys
+        .iter()
+        .map(|key| {
+            [
+                file_name(&key.public_key_file),
+                key.private_key_file
+                    .as_deref()
+                    .map(|file| file_name(file))
+                    .unwrap_or_else(|| "-".to_string()),
+                key.key_type.to_string(),
+                format!(
+                    "{}...",
+                    &key.public_key[..LISTED_PUBLIC_KEY_PREFIX_LENGTH.min(key.public_key.len())]
+                ),
+                key.account_address
+                    .map(|address| address.to_hex_literal())
+                    .unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+    let header = [
+        "PUBLIC KEY FILE",
+        "PRIVATE KEY FILE",
+        "TYPE",
+        "PUBLIC KEY",
+        "ADDRESS",
+    ]
+    .map(String::from);
+
+    let mut widths = header.clone().map(|column| column.len());
+    for row in &rows {
+        for (width, column) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(column.len());
+        }
+    }
+    let format_row = |row: &[String; 5]| {
+        row.iter()
+            .zip(widths.iter())
+            .map(|(column, width)| format!("{:<width$}", column, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![format_row(&header)];
+    lines.extend(rows.iter().map(format_row));
+    lines.extend(
+        key_list
+            .skipped
+            .iter()
+            .map(|skipped| format!("Skipped {}: {}", file_name(&skipped.file), skipped.error)),
+    );
+    lines
+}
+
+/// Converts a key file from one encoding to another
+///
+/// `convert --output-encoding <encoding>` is `export --format <encoding> --part private` for a
+/// private key, and `export --format <encoding>` for a public key.
+///
+/// Public keys are converted without needing the private key, and are taken to be keys of
+/// files ending in `.pub` unless `--public-key` is given.  A private key is always saved
+/// unencrypted, and readable only by the user.  The key is read from stdin and the converted
+/// key written to stdout unless `--input` or `--output` are given, though a private key is only
+/// written to stdout with `--print-private`.
+#[derive(Debug, Parser)]
+pub struct Convert {
+    #[clap(flatten)]
+    io_options: IoOptions,
+    /// Key type: `x25519`, `ed25519`, `secp256k1`, or `secp256r1`
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyType,
+    /// The key file holds a public key
+    #[clap(long)]
+    public_key: bool,
+    /// Encoding of the key file as `base64`, `base64url`, `bcs`, `bech32`, `der`, `hex`, `pem`,
+    /// `raw`, or `auto` to detect it
+    #[clap(long)]
+    input_encoding: EncodingType,
+    /// Encoding of the converted key as `base64`, `base64url`, `bcs`, `bech32`, `der`, `hex`,
+    /// `pem`, or `raw`
+    #[clap(long)]
+    output_encoding: EncodingType,
+    #[clap(flatten)]
+    checksum_options: ChecksumOptions,
+    #[clap(flatten)]
+    passphrase_options: PassphraseOptions,
+    #[clap(flatten)]
+    prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<HashMap<&'static str, PathBuf>> for Convert {
+    fn command_name(&self) -> &'static str {
+        "Convert"
+    }
+
+    async fn execute(self) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
+        let is_public_key = self.public_key
+            || self.io_options.input.extension() == Some(OsStr::new(PUBLIC_KEY_EXTENSION));
+        self.io_options
+            .check_output(self.prompt_options, !is_public_key)?;
+
+        let encoded_key = visit_key_type(&self, self.key_type, is_public_key)?;
+
+        let mut map = HashMap::new();
+        let output_file =
+            self.io_options
+                .write_output("converted key", &encoded_key, !is_public_key)?;
+        if let Some(output_file) = output_file {
+            if is_public_key {
+                map.insert("PublicKey Path", output_file);
+            } else {
+                map.insert("PrivateKey Path", output_file);
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl Convert {
+    /// Loads the key as a `Key` and encodes it with the output encoding
+    fn convert<Key: ValidCryptoMaterial + KeyFormat>(&self) -> CliTypedResult<Zeroizing<Vec<u8>>> {
+        let input = KeyFile::new(&self.io_options.input, self.input_encoding)
+            .with_passphrase_options(self.passphrase_options.clone());
+        let key: Key = self.checksum_options.load(&input, "--input")?;
+        self.output_encoding.encode_key("--output", &key)
+    }
+}
+
+impl KeyTypeVisitor for Convert {
+    type Output = Zeroizing<Vec<u8>>;
+
+    fn visit_private_key<Key>(&self) -> CliTypedResult<Self::Output>
+    where
+        Key: PrivateKey + ValidCryptoMaterial + KeyFormat,
+        Key::PublicKeyMaterial: ValidCryptoMaterial + KeyFormat,
+    {
+        self.convert::<Key>()
+    }
+
+    fn visit_public_key<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+    ) -> CliTypedResult<Self::Output> {
+        self.convert::<Key>()
+    }
+}
+
+/// Derives the authentication key and account address of an `ed25519` key
+///
+/// The key can be a public key, or a private key whose public key is used.  If neither is
+/// given, the private key of the profile is used.  The authentication key is for the single key
+/// scheme, and the account address is the one derived from it.
+#[derive(Debug, Parser)]
+pub struct AuthKey {
+    #[clap(flatten)]
+    public_key_input_options: PublicKeyInputOptions,
+    #[clap(flatten)]
+    private_key_input_options: PrivateKeyInputOptions,
+    /// Key type, only `ed25519` keys are account keys
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyType,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    profile_options: ProfileOptions,
+}
+
+/// Authentication key and account address, as shown by `auth-key`
+#[derive(Debug, Serialize)]
+pub struct AuthKeyInfo {
+    /// Authentication key in hex
+    authentication_key: String,
+    account_address: AccountAddress,
+}
+
+#[async_trait]
+impl CliCommand<AuthKeyInfo> for AuthKey {
+    fn command_name(&self) -> &'static str {
+        "AuthKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<AuthKeyInfo> {
+        if !matches!(self.key_type, KeyType::Ed25519) {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} keys aren't account keys, only ed25519 keys have an authentication key",
+                self.key_type.name()
+            )));
+        }
+        if self.public_key_input_options.is_given() && self.private_key_input_options.is_given() {
+            return Err(CliError::CommandArgumentError(
+                "Only one of a public key or a private key can be given".to_string(),
+            ));
+        }
+
+        let profile = &self.profile_options.profile;
+        let public_key = if self.public_key_input_options.is_given() {
+            self.public_key_input_options
+                .extract_public_key(&self.encoding_options, profile)?
+        } else {
+            self.private_key_input_options
+                .extract_public_key(&self.encoding_options, profile)?
+        };
+
+        Ok(AuthKeyInfo::of(&public_key))
+    }
+}
+
+impl AuthKeyInfo {
+    /// Derives the single key scheme authentication key of a public key, and its account
+    /// address
+    pub fn of(public_key: &ed25519::Ed25519PublicKey) -> AuthKeyInfo {
+        Self::of_authentication_key(AuthenticationKey::ed25519(public_key))
+    }
+
+    /// Derives the multi-ed25519 scheme authentication key of a multi-ed25519 public key, and
+    /// its account address
+    pub fn of_multi(public_key: &MultiEd25519PublicKey) -> AuthKeyInfo {
+        Self::of_authentication_key(AuthenticationKey::multi_ed25519(public_key))
+    }
+
+    fn of_authentication_key(authentication_key: AuthenticationKey) -> AuthKeyInfo {
+        AuthKeyInfo {
+            authentication_key: hex::encode(authentication_key.to_vec()),
+            account_address: authentication_key.derived_address(),
+        }
+    }
+}
+
+/// Authentication scheme of an account, whose byte is appended to the public key as a domain
+/// separator when deriving its authentication key
+///
+/// Only the `Ed25519` and `MultiEd25519` schemes of `aptos_types` exist, so `multi_agg` is
+/// parsed only to be rejected as unsupported.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthScheme {
+    /// An `ed25519` key on its own
+    Single,
+    /// The multi-ed25519 key of several `ed25519` keys
+    Multi,
+    /// An aggregated multi-key, which has no scheme yet
+    MultiAgg,
+}
+
+impl AuthScheme {
+    /// All schemes, in the order they're listed in messages
+    pub const ALL: &'static [AuthScheme] =
+        &[AuthScheme::Single, AuthScheme::Multi, AuthScheme::MultiAgg];
+
+    /// Name of the scheme, as used in messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            AuthScheme::Single => "single",
+            AuthScheme::Multi => "multi",
+            AuthScheme::MultiAgg => "multi_agg",
+        }
+    }
+}
+
+impl FromStr for AuthScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_by_name(
+            s,
+            "authentication scheme",
+            AuthScheme::ALL,
+            AuthScheme::name,
+        )
+    }
+}
+
+/// Checks an account address is the one derived from a public key
+///
+/// The address derived from the single key scheme authentication key of the public key is
+/// compared with `--address`, and the command fails if they don't match.
+#[derive(Debug, Parser)]
+pub struct ValidateAddress {
+    #[clap(flatten)]
+    public_key_input_options: PublicKeyInputOptions,
+    /// Account address in hex, with or without `0x`, and padded with leading zeros if short
+    #[clap(long, parse(try_from_str = parse_address))]
+    address: AccountAddress,
+    /// Key type, only `ed25519` keys are account keys
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyType,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+}
+
+/// A matching account address, as shown by `validate-address`
+#[derive(Debug, Serialize)]
+pub struct AddressMatch {
+    account_address: AccountAddress,
+    /// Authentication key in hex
+    authentication_key: String,
+}
+
+#[async_trait]
+impl CliCommand<AddressMatch> for ValidateAddress {
+    fn command_name(&self) -> &'static str {
+        "ValidateAddress"
+    }
+
+    async fn execute(self) -> CliTypedResult<AddressMatch> {
+        if !matches!(self.key_type, KeyType::Ed25519) {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} keys aren't account keys, only ed25519 keys have an account address",
+                self.key_type.name()
+            )));
+        }
+
+        let public_key = self
+            .public_key_input_options
+            .extract_public_key(&self.encoding_options, "")?;
+        let authentication_key = AuthenticationKey::ed25519(&public_key);
+        let derived_address = authentication_key.derived_address();
+        if !constant_time_eq(&derived_address.to_vec(), &self.address.to_vec()) {
+            return Err(CliError::UnexpectedError(format!(
+                "Address mismatch: {} isn't the address {} of the public key",
+                self.address.to_hex_literal(),
+                derived_address.to_hex_literal()
+            )));
+        }
+        Ok(AddressMatch {
+            account_address: derived_address,
+            authentication_key: hex::encode(authentication_key.to_vec()),
+        })
+    }
+}
+
+/// Parses an account address in hex, with or without `0x`, left padding short addresses
+pub fn parse_address(str: &str) -> CliTypedResult<AccountAddress> {
+    let hex = str.strip_prefix("0x").unwrap_or(str);
+    if hex.is_empty() || hex.len() > 2 * AccountAddress::LENGTH {
+        return Err(CliError::CommandArgumentError(format!(
+            "Invalid account address '{}', expected 1 to {} hex digits",
+            str,
+            2 * AccountAddress::LENGTH
+        )));
+    }
+    let padded = format!("{:0>width$}", hex, width = 2 * AccountAddress::LENGTH);
+    AccountAddress::from_hex(padded).map_err(|err| {
+        CliError::CommandArgumentError(format!("Invalid account address '{}': {}", str, err))
+    })
+}
+
+/// Saves the public key of a private key file, e.g. when the `.pub` file has been lost
+///
+/// The private key is read from `--input`, and the public key is encoded with the same encoding
+/// and saved to `--output`, which defaults to the public key file `generate` would have saved.
+/// An `--output` of `-` writes the public key to stdout instead.
+///
+/// With `--recover`, an existing public key file is checked against the private key, as it may
+/// have been corrupted or tampered with.  A matching file is left as is, and both public keys
+/// of a mismatched one are shown before it's replaced.
+#[derive(Debug, Parser)]
+pub struct DerivePublicKey {
+    #[clap(flatten)]
+    io_options: IoOptions,
+    /// Key type: `x25519`, `ed25519`, `secp256k1`, or `secp256r1`
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyType,
+    /// Check an existing public key file against the private key, only replacing it if it
+    /// doesn't match
+    ///
+    /// Replacing it is still prompted for, unless using `--assume-yes`.
+    #[clap(long)]
+    recover: bool,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    checksum_options: ChecksumOptions,
+    #[clap(flatten)]
+    passphrase_options: PassphraseOptions,
+    #[clap(flatten)]
+    prompt_options: PromptOptions,
+}
+
+/// A saved public key file, as shown by `public-key`
+#[derive(Debug, Default, Serialize)]
+pub struct DerivedPublicKey {
+    #[serde(rename = "PublicKey Path", skip_serializing_if = "Option::is_none")]
+    public_key_file: Option<PathBuf>,
+    /// Public key of the private key in hex, with `--recover`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    public_key: Option<String>,
+    /// What was done with `--recover`: `created`, `unchanged`, or `replaced`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<&'static str>,
+    /// The public key that was replaced in hex, or why it couldn't be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_public_key: Option<String>,
+}
+
+#[async_trait]
+impl CliCommand<DerivedPublicKey> for DerivePublicKey {
+    fn command_name(&self) -> &'static str {
+        "PublicKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<DerivedPublicKey> {
+        if self.writes_to_stdout() {
+            if self.recover {
+                return Err(CliError::CommandArgumentError(
+                    "--recover checks a public key file, so it can't write to stdout".to_string(),
+                ));
+            }
+            let encoded_public_key = self.dispatch_public_key(None)?.0;
+            self.io_options
+                .write_output("public key", &encoded_public_key, false)?;
+            return Ok(DerivedPublicKey::default());
+        }
+
+        let output_file = match self.io_options.output {
+            Some(ref output_file) => output_file.clone(),
+            None if self.io_options.input == Path::new("-") => {
+                return Err(CliError::CommandArgumentError(
+                    "--output must be given when reading the private key from stdin".to_string(),
+                ))
+            }
+            None => public_key_file_of(&self.io_options.input)?,
+        };
+        // A recovered file is only prompted for once it's known not to match
+        if !self.recover {
+            check_if_file_exists(&output_file, self.prompt_options)?;
+        }
+        let (encoded_public_key, mut derived) = self.dispatch_public_key(Some(&output_file))?;
+        if derived.status != Some("unchanged") {
+            write_to_public_file(&output_file, "--output", &encoded_public_key)?;
+        }
+        derived.public_key_file = Some(output_file);
+        Ok(derived)
+    }
+}
+
+impl DerivePublicKey {
+    /// Whether the public key is written to stdout, only with an `--output` of `-`
+    fn writes_to_stdout(&self) -> bool {
+        matches!(self.io_options.output, Some(ref output) if is_stdout_path(output))
+    }
+
+    /// Loads the private key as a key of the key type, and encodes its public key
+    ///
+    /// With `--recover`, the public key is also checked against the one in `output_file`.
+    fn dispatch_public_key(
+        &self,
+        output_file: Option<&Path>,
+    ) -> CliTypedResult<(Zeroizing<Vec<u8>>, DerivedPublicKey)> {
+        match self.key_type {
+            KeyType::X25519 => self.public_key::<x25519::PrivateKey>(output_file),
+            KeyType::Ed25519 => self.public_key::<ed25519::Ed25519PrivateKey>(output_file),
+            KeyType::Secp256k1 => self.public_key::<secp256k1::Secp256k1PrivateKey>(output_file),
+            KeyType::Secp256r1 => self.public_key::<secp256r1::Secp256r1PrivateKey>(output_file),
+        }
+    }
+
+    /// Loads the private key as a `Key` and encodes its public key
+    fn public_key<Key>(
+        &self,
+        output_file: Option<&Path>,
+    ) -> CliTypedResult<(Zeroizing<Vec<u8>>, DerivedPublicKey)>
+    where
+        Key: PrivateKey + ValidCryptoMaterial + KeyFormat,
+        Key::PublicKeyMaterial: KeyFormat,
+    {
+        let input = self
+            .encoding_options
+            .key_file(&self.io_options.input)
+            .with_key_type(self.key_type)
+            .with_passphrase_options(self.passphrase_options.clone());
+        let private_key: Key = self.checksum_options.load(&input, "--input")?;
+        let public_key = private_key.public_key();
+        let encoded_public_key = self.encoding_options.encoding.encode_key_with(
+            "--output",
+            &public_key,
+            KeyEncodeOptions {
+                bech32_hrp: self.encoding_options.bech32_hrp.clone(),
+                ..KeyEncodeOptions::default()
+            },
+        )?;
+        let derived = match output_file {
+            Some(output_file) if self.recover => {
+                self.recover::<Key::PublicKeyMaterial>(output_file, &public_key.to_bytes())?
+            }
+            _ => DerivedPublicKey::default(),
+        };
+        Ok((encoded_public_key, derived))
+    }
+
+    /// Checks an existing public key file against the public key, for `--recover`
+    ///
+    /// A mismatched file is shown, and prompted for before it's replaced.
+    fn recover<PublicKey: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        output_file: &Path,
+        public_key_bytes: &[u8],
+    ) -> CliTypedResult<DerivedPublicKey> {
+        let public_key = hex::encode(public_key_bytes);
+        if !output_file.exists() {
+            return Ok(DerivedPublicKey {
+                public_key: Some(public_key),
+                status: Some("created"),
+                ..DerivedPublicKey::default()
+            });
+        }
+
+        let previous = self
+            .encoding_options
+            .key_file(output_file)
+            .load::<PublicKey>("public key")
+            .map(|previous| previous.to_bytes());
+        if matches!(previous, Ok(ref previous) if constant_time_eq(previous, public_key_bytes)) {
+            return Ok(DerivedPublicKey {
+                public_key: Some(public_key),
+                status: Some("unchanged"),
+                ..DerivedPublicKey::default()
+            });
+        }
+
+        let previous = previous
+            .map(hex::encode)
+            .unwrap_or_else(|err| format!("unreadable: {}", err));
+        eprintln!(
+            "WARNING: {} doesn't match the private key {}\n  Existing public key: {}\n  \
+            Public key of the private key: {}",
+            output_file.display(),
+            self.io_options.input.display(),
+            previous,
+            public_key
+        );
+        check_if_file_exists(output_file, self.prompt_options)?;
+        Ok(DerivedPublicKey {
+            public_key: Some(public_key),
+            status: Some("replaced"),
+            previous_public_key: Some(previous),
+            ..DerivedPublicKey::default()
+        })
+    }
+}
+
+/// Builds a signed proposal to rotate an account's `ed25519` key to a new key
+///
+/// The proposal is a `RotationProofChallenge` for the account, signed by both the current key
+/// and the new key, proving the owner of each agrees to the rotation.  It's BCS serialized,
+/// encoded with the `encoding`, and printed, or saved to `output-file` for submission by
+/// another tool.  BCS proposals are printed in hex.
+#[derive(Debug, Parser)]
+pub struct RotateProposal {
+    #[clap(flatten)]
+    private_key_input_options: PrivateKeyInputOptions,
+    /// New private key input file name, or `-` to read from stdin
+    #[clap(long, parse(from_os_str))]
+    new_private_key_file: PathBuf,
+    /// Address of the account, defaults to the address derived from the current key
+    #[clap(long, parse(try_from_str=crate::common::types::load_account_arg))]
+    account_address: Option<AccountAddress>,
+    /// Current sequence number of the account
+    #[clap(long)]
+    sequence_number: u64,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    profile_options: ProfileOptions,
+    /// Output file name for the encoded proposal
+    #[clap(long, parse(from_os_str))]
+    output_file: Option<PathBuf>,
+}
+
+const ROTATION_PROPOSAL_PEM_LABEL: &str = "APTOS ROTATION PROPOSAL";
+
+/// `0x1::type_info::TypeInfo`, which prefixes a Move struct signed with
+/// `ed25519::signature_verify_strict_t`
+#[derive(Debug, Serialize)]
+struct TypeInfo {
+    account_address: AccountAddress,
+    module_name: Vec<u8>,
+    struct_name: Vec<u8>,
+}
+
+/// What both keys sign to agree to a rotation, `0x1::account::RotationProofChallenge`
+///
+/// The fields are those of the Move struct, in the same order, so the BCS is the same.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RotationProofChallenge {
+    pub sequence_number: u64,
+    /// Address of the account whose key is rotated
+    pub originator: AccountAddress,
+    /// Authentication key of the current key, which is rotated away from, as an address
+    pub current_auth_key: AccountAddress,
+    pub new_public_key: Vec<u8>,
+}
+
+impl RotationProofChallenge {
+    /// The bytes signed by each key, the `TypeInfo` of the Move struct followed by the challenge
+    pub fn signing_message(&self) -> CliTypedResult<Vec<u8>> {
+        let type_info = TypeInfo {
+            account_address: AccountAddress::ONE,
+            module_name: b"account".to_vec(),
+            struct_name: b"RotationProofChallenge".to_vec(),
+        };
+        let mut message =
+            bcs::to_bytes(&type_info).map_err(|err| CliError::BCS("challenge", err))?;
+        message.extend(bcs::to_bytes(self).map_err(|err| CliError::BCS("challenge", err))?);
+        Ok(message)
+    }
+}
+
+/// A challenge signed by both the current and the new key
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RotationProposal {
+    pub challenge: RotationProofChallenge,
+    pub current_public_key: ed25519::Ed25519PublicKey,
+    pub current_signature: ed25519::Ed25519Signature,
+    pub new_signature: ed25519::Ed25519Signature,
+}
+
+#[async_trait]
+impl CliCommand<String> for RotateProposal {
+    fn command_name(&self) -> &'static str {
+        "RotateProposal"
+    }
+
+    async fn execute(self) -> CliTypedResult<String> {
+        let encoding = self.encoding_options.encoding;
+        let current_key = self
+            .private_key_input_options
+            .extract_private_key(&self.encoding_options, &self.profile_options.profile)?;
+        let new_key: ed25519::Ed25519PrivateKey = self
+            .encoding_options
+            .key_file(&self.new_private_key_file)
+            .with_key_type(KeyType::Ed25519)
+            .with_passphrase_options(self.private_key_input_options.passphrase_options().clone())
+            .load("--new-private-key-file")?;
+        if constant_time_eq(
+            &current_key.public_key().to_bytes(),
+            &new_key.public_key().to_bytes(),
+        ) {
+            return Err(CliError::CommandArgumentError(
+                "The new key is the same as the current key".to_string(),
+            ));
+        }
+
+        // The authentication key is the address derived from it
+        let current_auth_key =
+            AuthenticationKey::ed25519(&current_key.public_key()).derived_address();
+        let challenge = RotationProofChallenge {
+            sequence_number: self.sequence_number,
+            originator: self.account_address.unwrap_or(current_auth_key),
+            current_auth_key,
+            new_public_key: new_key.public_key().to_bytes().to_vec(),
+        };
+        let message = challenge.signing_message()?;
+        let proposal = RotationProposal {
+            current_public_key: current_key.public_key(),
+            current_signature: sign_message(&current_key, &message)?,
+            new_signature: sign_message(&new_key, &message)?,
+            challenge,
+        };
+
+        let proposal = bcs::to_bytes(&proposal).map_err(|err| CliError::BCS("proposal", err))?;
+        let encoded_proposal =
+            encoding.encode_bytes("proposal", ROTATION_PROPOSAL_PEM_LABEL, &proposal)?;
+        if let Some(ref output_file) = self.output_file {
+            write_to_file(output_file, "proposal", &encoded_proposal)?;
+        }
+
+        // BCS isn't printable, so show the BCS bytes as hex instead
+        match encoding {
+            EncodingType::BCS | EncodingType::Raw => Ok(hex::encode_upper(&encoded_proposal)),
+            _ => Ok(String::from_utf8(encoded_proposal)?),
+        }
+    }
+}
+
+/// Shows a short fingerprint of a public key, for checking a key exchanged out of band
+///
+/// A private key has the fingerprint of its public key, so both ends of an exchange get the
+/// same fingerprint.  Public keys are taken to be keys of files ending in `.pub` unless
+/// `--public-key` is given.
+///
+/// `--compare` checks another key file has the same public key, e.g. after copying a key to
+/// another machine, and fails with exit code 1 if it doesn't, as `diff` does.
+#[derive(Debug, Parser)]
+pub struct Fingerprint {
+    /// Key input file name, or `-` to read from stdin
+    #[clap(long, parse(from_os_str))]
+    key_file: PathBuf,
+    /// Key type: `x25519`, `ed25519`, `secp256k1`, `secp256r1`, or `auto` to detect it
+    ///
+    /// `auto` tries `ed25519`, `x25519`, `secp256k1`, then `secp256r1`, and shows the first key
+    /// type that the key loaded as.  Any other key types it also loads as are shown, with a
+    /// warning, as the key may be of one of them instead, e.g. any private key loads as
+    /// `ed25519`.
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyTypeSelection,
+    /// The key file holds a public key
+    #[clap(long)]
+    public_key: bool,
+    /// Also show the fingerprint as 12 BIP39 words, which are easier to read aloud
+    #[clap(long)]
+    words: bool,
+    /// Another key file of the key type to compare with `--key-file`, or `-` to read from stdin
+    ///
+    /// Both fingerprints are shown, and the command fails unless both files have the same
+    /// public key.  Either file can hold a private key or a public key.
+    #[clap(long, parse(from_os_str))]
+    compare: Option<PathBuf>,
+    /// Encoding of the `--compare` file, defaults to the `encoding`
+    #[clap(long, requires = "compare")]
+    other_encoding: Option<EncodingType>,
+    /// The `--compare` file holds a public key
+    #[clap(long, requires = "compare")]
+    other_public_key: bool,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    checksum_options: ChecksumOptions,
+    #[clap(flatten)]
+    passphrase_options: PassphraseOptions,
+}
+
+/// Bytes of the SHA-256 of a public key shown in its fingerprint
+///
+/// 16 bytes is 128 bits, so the chance of two given keys having the same fingerprint is
+/// 2^-128, and a collision among any of the keys only becomes likely at around 2^64 keys.  It's
+/// also exactly the entropy of a 12 word BIP39 mnemonic.
+pub const FINGERPRINT_LENGTH: usize = 16;
+
+/// Fingerprint of a key, as shown by `fingerprint`
+#[derive(Debug, Serialize)]
+pub struct KeyFingerprint {
+    /// Bytes of the fingerprint in hex, separated by `:`
+    fingerprint: String,
+    /// The fingerprint as BIP39 words, only with `--words`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    words: Option<String>,
+    /// Fingerprint of the `--compare` file, which is the same as `fingerprint`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    other_fingerprint: Option<String>,
+    /// Key type the key was detected as, only with `--key-type auto`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key_type: Option<&'static str>,
+    /// Other key types the key also loads as with `--key-type auto`, so it may be of one of them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    other_key_types: Vec<&'static str>,
+}
+
+#[async_trait]
+impl CliCommand<KeyFingerprint> for Fingerprint {
+    fn command_name(&self) -> &'static str {
+        "Fingerprint"
+    }
+
+    async fn execute(self) -> CliTypedResult<KeyFingerprint> {
+        let key_file = self
+            .encoding_options
+            .key_file(&self.key_file)
+            .with_passphrase_options(self.passphrase_options.clone());
+        let (key_type, public_key, other_key_types) =
+            self.detect_public_key(&key_file, "--key-file", self.public_key)?;
+        if !other_key_types.is_empty() {
+            eprintln!(
+                "WARNING: the key loads as {}, but also as {}, use --key-type if it's one of them",
+                key_type,
+                other_key_types
+                    .iter()
+                    .map(KeyType::name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        let fingerprint = fingerprint(&public_key);
+        let words = if self.words {
+            Some(mnemonic::fingerprint_words(&fingerprint)?)
+        } else {
+            None
+        };
+        let mut key_fingerprint = KeyFingerprint {
+            fingerprint: format_fingerprint(&fingerprint),
+            words,
+            other_fingerprint: None,
+            key_type: match self.key_type {
+                KeyTypeSelection::Auto => Some(key_type.name()),
+                KeyTypeSelection::Only(_) => None,
+            },
+            other_key_types: other_key_types.iter().map(KeyType::name).collect(),
+        };
+
+        if let Some(ref compare) = self.compare {
+            if is_stdout_path(compare) && is_stdout_path(&self.key_file) {
+                return Err(CliError::CommandArgumentError(
+                    "--key-file and --compare can't both be read from stdin".to_string(),
+                ));
+            }
+            let encoding = self
+                .other_encoding
+                .unwrap_or(self.encoding_options.encoding);
+            let other_file = KeyFile::new(compare, encoding)
+                .with_key_type(key_type)
+                .with_bech32_hrp(self.encoding_options.bech32_hrp.clone())
+                .with_passphrase_options(self.passphrase_options.clone());
+            let other_public_key =
+                self.public_key_of(&other_file, "--compare", self.other_public_key)?;
+            let other_fingerprint = format_fingerprint(&self::fingerprint(&other_public_key));
+            if !constant_time_eq(&other_public_key, &public_key) {
+                return Err(CliError::KeysDiffer(format!(
+                    "{} has the fingerprint {}, {} has the fingerprint {}",
+                    self.key_file.display(),
+                    key_fingerprint.fingerprint,
+                    compare.display(),
+                    other_fingerprint
+                )));
+            }
+            key_fingerprint.other_fingerprint = Some(other_fingerprint);
+        }
+        Ok(key_fingerprint)
+    }
+}
+
+impl Fingerprint {
+    /// Loads the key of a key file as each of the `--key-type`s, returning the first key type
+    /// it loaded as, the bytes of its public key, and the other key types it also loaded as
+    ///
+    /// Only failing to parse the key moves on to the next key type, other errors are returned.
+    fn detect_public_key(
+        &self,
+        key_file: &KeyFile,
+        name: &'static str,
+        is_public_key: bool,
+    ) -> CliTypedResult<(KeyType, Vec<u8>, Vec<KeyType>)> {
+        let key_types = self.key_type.candidates();
+        if key_types.len() > 1 && is_stdout_path(&key_file.path) {
+            return Err(CliError::CommandArgumentError(
+                "--key-type auto can't read the key from stdin, as it's read once per key type"
+                    .to_string(),
+            ));
+        }
+        let mut detected = None;
+        let mut other_key_types = Vec::new();
+        for key_type in key_types {
+            let typed_key_file = key_file.clone().with_key_type(*key_type);
+            match self.public_key_of(&typed_key_file, name, is_public_key) {
+                Ok(public_key) if detected.is_none() => detected = Some((*key_type, public_key)),
+                Ok(_) => other_key_types.push(*key_type),
+                Err(CliError::UnableToParse(_, _)) | Err(CliError::BCS(_, _))
+                    if key_types.len() > 1 => {}
+                Err(err) => return Err(err),
+            }
+        }
+        if let Some((key_type, public_key)) = detected {
+            return Ok((key_type, public_key, other_key_types));
+        }
+        Err(CliError::UnableToParse(
+            name,
+            format!(
+                "it isn't a key of any key type, tried {}",
+                key_types
+                    .iter()
+                    .map(KeyType::name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ))
+    }
+
+    /// Loads the key of a key file of its key type, returning the bytes of its public key
+    ///
+    /// The key is a public key with `is_public_key`, or if the file ends in `.pub`.
+    fn public_key_of(
+        &self,
+        key_file: &KeyFile,
+        name: &'static str,
+        is_public_key: bool,
+    ) -> CliTypedResult<Vec<u8>> {
+        let is_public_key =
+            is_public_key || key_file.path.extension() == Some(OsStr::new(PUBLIC_KEY_EXTENSION));
+        let key_type = key_file.key_type.ok_or_else(|| {
+            CliError::UnexpectedError(format!("{} has no key type to load", name))
+        })?;
+        let loader = LoadKeyBytes {
+            checksum_options: &self.checksum_options,
+            key_file,
+            name,
+        };
+        Ok(visit_key_type(&loader, key_type, is_public_key)?.public_key)
+    }
+}
+
+/// Computes the fingerprint of the bytes of a public key, the start of their SHA-256
+pub fn fingerprint(public_key: &[u8]) -> [u8; FINGERPRINT_LENGTH] {
+    let mut fingerprint = [0u8; FINGERPRINT_LENGTH];
+    fingerprint.copy_from_slice(&Sha256::digest(public_key)[..FINGERPRINT_LENGTH]);
+    fingerprint
+}
+
+/// Formats a fingerprint as `ab:cd:ef:...`
+pub fn format_fingerprint(fingerprint: &[u8]) -> String {
+    fingerprint
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Checks two key files hold the same key, even if they're in different encodings
+///
+/// Both keys are loaded and their canonical bytes compared in constant time.  Unlike
+/// `fingerprint --compare`, private keys are compared themselves rather than by their public
+/// keys.  Each file holds a public key if it ends in `.pub`, or with `--public-key`, and a
+/// private key can't be compared with a public key.
+///
+/// Like `diff`, the command succeeds if the keys are the same, fails with exit code 1 if they
+/// differ, and with another exit code if a key can't be loaded, e.g. 65 for a checksum mismatch.
+#[derive(Debug, Parser)]
+pub struct DiffKeys {
+    /// Key input file name, or `-` to read from stdin
+    #[clap(long, parse(from_os_str))]
+    key_file: PathBuf,
+    /// Key input file name of the key to compare with `--key-file`, or `-` to read from stdin
+    #[clap(long, parse(from_os_str))]
+    other_key_file: PathBuf,
+    /// Key type of both keys: `x25519`, `ed25519`, `secp256k1`, or `secp256r1`
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyType,
+    /// The key files hold public keys
+    #[clap(long)]
+    public_key: bool,
+    /// Encoding of the `--other-key-file`, defaults to the `encoding`
+    #[clap(long)]
+    other_encoding: Option<EncodingType>,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    checksum_options: ChecksumOptions,
+    #[clap(flatten)]
+    passphrase_options: PassphraseOptions,
+}
+
+#[async_trait]
+impl CliCommand<()> for DiffKeys {
+    fn command_name(&self) -> &'static str {
+        "DiffKeys"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        if is_stdout_path(&self.key_file) && is_stdout_path(&self.other_key_file) {
+            return Err(CliError::CommandArgumentError(
+                "--key-file and --other-key-file can't both be read from stdin".to_string(),
+            ));
+        }
+        let is_public_key = self.is_public_key(&self.key_file);
+        if is_public_key != self.is_public_key(&self.other_key_file) {
+            return Err(CliError::CommandArgumentError(format!(
+                "A private key can't be compared with a public key, {} and {} hold one of each",
+                self.key_file.display(),
+                self.other_key_file.display()
+            )));
+        }
+        let key = self.key_bytes(
+            &self
+                .encoding_options
+                .key_file(&self.key_file)
+                .with_passphrase_options(self.passphrase_options.clone()),
+            "--key-file",
+            is_public_key,
+        )?;
+        let other_key = self.key_bytes(
+            &KeyFile::new(
+                &self.other_key_file,
+                self.other_encoding
+                    .unwrap_or(self.encoding_options.encoding),
+            )
+            .with_passphrase_options(self.passphrase_options.clone())
+            .with_bech32_hrp(self.encoding_options.bech32_hrp.clone()),
+            "--other-key-file",
+            is_public_key,
+        )?;
+        if !constant_time_eq(&key, &other_key) {
+            return Err(CliError::KeysDiffer(format!(
+                "{} and {} don't hold the same key",
+                self.key_file.display(),
+                self.other_key_file.display()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl DiffKeys {
+    /// Whether a key file holds a public key, with `--public-key` or if it ends in `.pub`
+    fn is_public_key(&self, path: &Path) -> bool {
+        self.public_key || path.extension() == Some(OsStr::new(PUBLIC_KEY_EXTENSION))
+    }
+
+    /// Loads the key of a key file as a key of the key type, returning its canonical bytes
+    fn key_bytes(
+        &self,
+        key_file: &KeyFile,
+        name: &'static str,
+        is_public_key: bool,
+    ) -> CliTypedResult<Zeroizing<Vec<u8>>> {
+        let key_file = key_file.clone().with_key_type(self.key_type);
+        let loader = LoadKeyBytes {
+            checksum_options: &self.checksum_options,
+            key_file: &key_file,
+            name,
+        };
+        Ok(visit_key_type(&loader, self.key_type, is_public_key)?.key)
+    }
+}
+
+/// Exports an `ed25519` key in the OpenSSH formats, to use it as an SSH key
+///
+/// The public key is printed as an `authorized_keys` line, and saved to `output-file` if it's
+/// given.  The key can be a public key, or a private key whose public key is used.  With a
+/// private key, `private-key-output-file` saves it as an unencrypted OpenSSH private key,
+/// readable only by the user.
+#[derive(Debug, Parser)]
+pub struct ExportSsh {
+    #[clap(flatten)]
+    public_key_input_options: PublicKeyInputOptions,
+    #[clap(flatten)]
+    private_key_input_options: PrivateKeyInputOptions,
+    /// Key type, only `ed25519` keys can be SSH keys
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyType,
+    /// Comment ending the `authorized_keys` line, e.g. `user@host`
+    #[clap(long, parse(try_from_str = ssh::parse_comment))]
+    comment: Option<String>,
+    /// Output file name for the `authorized_keys` line
+    #[clap(long, parse(from_os_str))]
+    output_file: Option<PathBuf>,
+    /// Output file name for the OpenSSH private key
+    #[clap(long, parse(from_os_str))]
+    private_key_output_file: Option<PathBuf>,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    profile_options: ProfileOptions,
+    #[clap(flatten)]
+    prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<String> for ExportSsh {
+    fn command_name(&self) -> &'static str {
+        "ExportSsh"
+    }
+
+    async fn execute(self) -> CliTypedResult<String> {
+        if !matches!(self.key_type, KeyType::Ed25519) {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} keys can't be SSH keys, only ed25519 keys can",
+                self.key_type.name()
+            )));
+        }
+        if self.public_key_input_options.is_given() && self.private_key_input_options.is_given() {
+            return Err(CliError::CommandArgumentError(
+                "Only one of a public key or a private key can be given".to_string(),
+            ));
+        }
+        if self.private_key_output_file.is_some() && self.public_key_input_options.is_given() {
+            return Err(CliError::CommandArgumentError(
+                "--private-key-output-file needs a private key".to_string(),
+            ));
+        }
+        if let Some(ref output_file) = self.output_file {
+            check_if_file_exists(output_file, self.prompt_options)?;
+        }
+        if let Some(ref private_key_output_file) = self.private_key_output_file {
+            check_if_file_exists(private_key_output_file, self.prompt_options)?;
+        }
+
+        let profile = &self.profile_options.profile;
+        let comment = self.comment.as_deref();
+        let public_key = if self.public_key_input_options.is_given() {
+            self.public_key_input_options
+                .extract_public_key(&self.encoding_options, profile)?
+        } else {
+            let private_key = self
+                .private_key_input_options
+                .extract_private_key(&self.encoding_options, profile)?;
+            if let Some(ref private_key_output_file) = self.private_key_output_file {
+                let ssh_private_key = ssh::ssh_private_key(&private_key, comment);
+                write_to_user_only_file(
+                    private_key_output_file,
+                    "OpenSSH private key",
+                    ssh_private_key.as_bytes(),
+                )?;
+            }
+            private_key.public_key()
+        };
+
+        let line = ssh::ssh_public_key(&public_key, comment);
+        if let Some(ref output_file) = self.output_file {
+            write_to_public_file(
+                output_file,
+                "OpenSSH public key",
+                format!("{}\n", line).as_bytes(),
+            )?;
+        }
+        Ok(line)
+    }
+}
+
+/// Exports an `ed25519` public key as a JSON Web Key
+///
+/// The key can be a public key, or a private key whose public key is used.  The JWK is shown,
+/// and saved to `output-file` if it's given.
+#[derive(Debug, Parser)]
+pub struct ExportJwk {
+    #[clap(flatten)]
+    public_key_input_options: PublicKeyInputOptions,
+    #[clap(flatten)]
+    private_key_input_options: PrivateKeyInputOptions,
+    /// Key type, only `ed25519` keys can be exported as JWKs
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyType,
+    /// Key ID of the JWK
+    #[clap(long)]
+    kid: Option<String>,
+    /// Output file name for the JWK
+    #[clap(long, parse(from_os_str))]
+    output_file: Option<PathBuf>,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    profile_options: ProfileOptions,
+    #[clap(flatten)]
+    prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<Jwk> for ExportJwk {
+    fn command_name(&self) -> &'static str {
+        "ExportJwk"
+    }
+
+    async fn execute(self) -> CliTypedResult<Jwk> {
+        if !matches!(self.key_type, KeyType::Ed25519) {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} keys can't be exported as JWKs, only ed25519 keys can",
+                self.key_type.name()
+            )));
+        }
+        if self.public_key_input_options.is_given() && self.private_key_input_options.is_given() {
+            return Err(CliError::CommandArgumentError(
+                "Only one of a public key or a private key can be given".to_string(),
+            ));
+        }
+        if let Some(ref output_file) = self.output_file {
+            check_if_file_exists(output_file, self.prompt_options)?;
+        }
+
+        let profile = &self.profile_options.profile;
+        let public_key = if self.public_key_input_options.is_given() {
+            self.public_key_input_options
+                .extract_public_key(&self.encoding_options, profile)?
+        } else {
+            self.private_key_input_options
+                .extract_public_key(&self.encoding_options, profile)?
+        };
+
+        let jwk = Jwk::from_ed25519_public_key(&public_key, self.kid);
+        if let Some(ref output_file) = self.output_file {
+            let json = serde_json::to_string_pretty(&jwk)
+                .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+            write_to_public_file(output_file, "JWK", json.as_bytes())?;
+        }
+        Ok(jwk)
+    }
+}
+
+/// Formats a key can be exported in by `export`
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Hex encoded raw key
+    Hex,
+    /// Base 64 encoded raw key
+    Base64,
+    /// URL safe base 64 encoded raw key, without padding
+    Base64Url,
+    /// BCS serialized key
+    Bcs,
+    /// The raw key bytes
+    Raw,
+    /// DER of `ed25519` and `x25519` keys in PEM armor, or the raw key of other keys
+    Pem,
+    /// PKCS#8 or SubjectPublicKeyInfo DER, of `ed25519` and `x25519` keys only
+    Der,
+    /// JSON Web Key, of `ed25519` public keys only
+    Jwk,
+    /// OpenSSH `authorized_keys` line or private key file, of `ed25519` keys only
+    Ssh,
+    /// Bech32 with the `--bech32-hrp` human readable part
+    Bech32,
+}
+
+impl ExportFormat {
+    /// All formats, in the order they're listed in messages
+    pub const ALL: &'static [ExportFormat] = &[
+        ExportFormat::Hex,
+        ExportFormat::Base64,
+        ExportFormat::Base64Url,
+        ExportFormat::Bcs,
+        ExportFormat::Raw,
+        ExportFormat::Pem,
+        ExportFormat::Der,
+        ExportFormat::Jwk,
+        ExportFormat::Ssh,
+        ExportFormat::Bech32,
+    ];
+
+    /// Name of the format, as used in messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Hex => "hex",
+            ExportFormat::Base64 => "base64",
+            ExportFormat::Base64Url => "base64url",
+            ExportFormat::Bcs => "bcs",
+            ExportFormat::Raw => "raw",
+            ExportFormat::Pem => "pem",
+            ExportFormat::Der => "der",
+            ExportFormat::Jwk => "jwk",
+            ExportFormat::Ssh => "ssh",
+            ExportFormat::Bech32 => "bech32",
+        }
+    }
+
+    /// The encoding of the format, or `None` for the `ed25519` only formats
+    fn encoding(&self) -> Option<EncodingType> {
+        match self {
+            ExportFormat::Hex => Some(EncodingType::Hex),
+            ExportFormat::Base64 => Some(EncodingType::Base64),
+            ExportFormat::Base64Url => Some(EncodingType::Base64Url),
+            ExportFormat::Bcs => Some(EncodingType::BCS),
+            ExportFormat::Raw => Some(EncodingType::Raw),
+            ExportFormat::Pem => Some(EncodingType::Pem),
+            ExportFormat::Der => Some(EncodingType::Der),
+            ExportFormat::Bech32 => Some(EncodingType::Bech32),
+            ExportFormat::Jwk | ExportFormat::Ssh => None,
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_by_name(s, "export format", ExportFormat::ALL, ExportFormat::name)
+    }
+}
+
+/// Part of a key pair exported by `export`
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyPart {
+    /// The private key itself
+    Private,
+    /// The public key, of a private key or a public key
+    Public,
+}
+
+impl KeyPart {
+    /// All parts, in the order they're listed in messages
+    pub const ALL: &'static [KeyPart] = &[KeyPart::Private, KeyPart::Public];
+
+    /// Name of the part, as used in messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyPart::Private => "private",
+            KeyPart::Public => "public",
+        }
+    }
+}
+
+impl FromStr for KeyPart {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_by_name(s, "key part", KeyPart::ALL, KeyPart::name)
+    }
+}
+
+/// Exports a key, or the public key of a private key, in one of several formats
+///
+/// Public keys are taken to be keys of files ending in `.pub` unless `--public-key` is given,
+/// and only their public part can be exported.  `jwk` and `ssh` are only for `ed25519` keys,
+/// and `jwk` only for public keys.  A private key is always written unencrypted, and readable
+/// only by the user.  The key is read from stdin and written to stdout unless `--input` or
+/// `--output` are given, though a private key is only written to stdout with `--print-private`.
+///
+/// It covers the encodings of `convert` and the formats of `export-ssh` and `export-jwk`.
+#[derive(Debug, Parser)]
+pub struct ExportKey {
+    #[clap(flatten)]
+    io_options: IoOptions,
+    /// Key type: `x25519`, `ed25519`, `secp256k1`, or `secp256r1`
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyType,
+    /// The key file holds a public key
+    #[clap(long)]
+    public_key: bool,
+    /// Encoding of the key file as `base64`, `base64url`, `bcs`, `bech32`, `der`, `hex`, `pem`,
+    /// `raw`, or `auto` to detect it
+    #[clap(long, default_value = "auto")]
+    input_encoding: EncodingType,
+    /// Format to export the key in: `hex`, `base64`, `base64url`, `bcs`, `raw`, `pem`, `der`,
+    /// `jwk`, `ssh`, or `bech32`
+    #[clap(long)]
+    format: ExportFormat,
+    /// Part of the key to export: `private` or `public`
+    #[clap(long, default_value = "public")]
+    part: KeyPart,
+    /// Human readable part of `bech32` keys
+    #[clap(long, default_value = DEFAULT_BECH32_HRP)]
+    bech32_hrp: String,
+    /// Leave the `=` padding off `base64` keys, `base64url` keys are never padded
+    #[clap(long)]
+    base64_no_pad: bool,
+    /// Comment of `ssh` keys, e.g. `user@host`
+    #[clap(long, parse(try_from_str = ssh::parse_comment))]
+    comment: Option<String>,
+    /// Key ID of `jwk` keys
+    #[clap(long)]
+    kid: Option<String>,
+    #[clap(flatten)]
+    checksum_options: ChecksumOptions,
+    #[clap(flatten)]
+    passphrase_options: PassphraseOptions,
+    #[clap(flatten)]
+    prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<HashMap<&'static str, PathBuf>> for ExportKey {
+    fn command_name(&self) -> &'static str {
+        "ExportKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
+        let is_public_key = self.public_key
+            || self.io_options.input.extension() == Some(OsStr::new(PUBLIC_KEY_EXTENSION));
+        self.check_format(is_public_key)?;
+        self.io_options
+            .check_output(self.prompt_options, self.part == KeyPart::Private)?;
+
+        let exported = match self.format.encoding() {
+            Some(encoding) => {
+                let export = ExportWith {
+                    export_key: &self,
+                    encoding,
+                };
+                visit_key_type(&export, self.key_type, is_public_key)?
+            }
+            None => self.export_ed25519(is_public_key)?,
+        };
+
+        let is_private = self.part == KeyPart::Private;
+        let mut map = HashMap::new();
+        let output_file = self
+            .io_options
+            .write_output("exported key", &exported, is_private)?;
+        if let Some(output_file) = output_file {
+            if is_private {
+                map.insert("PrivateKey Path", output_file);
+            } else {
+                map.insert("PublicKey Path", output_file);
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl ExportKey {
+    /// Checks the format can hold the part of the key, before anything is read or written
+    fn check_format(&self, is_public_key: bool) -> CliTypedResult<()> {
+        if is_public_key && self.part == KeyPart::Private {
+            return Err(CliError::CommandArgumentError(
+                "A private key can't be exported from a public key".to_string(),
+            ));
+        }
+        match self.format {
+            ExportFormat::Jwk | ExportFormat::Ssh if self.key_type != KeyType::Ed25519 => {
+                Err(CliError::CommandArgumentError(format!(
+                    "{} keys can't be exported as {}, only ed25519 keys can",
+                    self.key_type,
+                    self.format.name()
+                )))
+            }
+            ExportFormat::Jwk if self.part == KeyPart::Private => {
+                Err(CliError::CommandArgumentError(
+                    "Private keys can't be exported as jwk, only public keys can".to_string(),
+                ))
+            }
+            ExportFormat::Der
+                if matches!(self.key_type, KeyType::Secp256k1 | KeyType::Secp256r1) =>
+            {
+                Err(CliError::CommandArgumentError(format!(
+                    "{} keys can't be exported as der, only ed25519 and x25519 keys can",
+                    self.key_type
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Loads the key file as a `Key`
+    fn load<Key: ValidCryptoMaterial + KeyFormat>(&self) -> CliTypedResult<Key> {
+        let input = KeyFile::new(&self.io_options.input, self.input_encoding)
+            .with_key_type(self.key_type)
+            .with_passphrase_options(self.passphrase_options.clone())
+            .with_bech32_hrp(self.bech32_hrp.clone());
+        self.checksum_options.load(&input, "--input")
+    }
+
+    fn encode_options(&self) -> KeyEncodeOptions {
+        KeyEncodeOptions {
+            bech32_hrp: self.bech32_hrp.clone(),
+            base64_no_pad: self.base64_no_pad,
+            ..KeyEncodeOptions::default()
+        }
+    }
+
+    /// Loads a public key as a `Key`, and encodes it with the encoding
+    fn export<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        encoding: EncodingType,
+    ) -> CliTypedResult<Zeroizing<Vec<u8>>> {
+        let key: Key = self.load()?;
+        encoding.encode_key_with("--output", &key, self.encode_options())
+    }
+
+    /// Loads a private key as a `Key`, and encodes the part of it with the encoding
+    fn export_of<Key>(&self, encoding: EncodingType) -> CliTypedResult<Zeroizing<Vec<u8>>>
+    where
+        Key: PrivateKey + ValidCryptoMaterial + KeyFormat,
+        Key::PublicKeyMaterial: ValidCryptoMaterial + KeyFormat,
+    {
+        let key: Key = self.load()?;
+        match self.part {
+            KeyPart::Private => encoding.encode_key_with("--output", &key, self.encode_options()),
+            KeyPart::Public => {
+                encoding.encode_key_with("--output", &key.public_key(), self.encode_options())
+            }
+        }
+    }
+
+    /// Exports an `ed25519` key as a JWK or an SSH key
+    fn export_ed25519(&self, is_public_key: bool) -> CliTypedResult<Zeroizing<Vec<u8>>> {
+        let comment = self.comment.as_deref();
+        let public_key = if is_public_key {
+            self.load::<ed25519::Ed25519PublicKey>()?
+        } else {
+            let private_key: ed25519::Ed25519PrivateKey = self.load()?;
+            if self.part == KeyPart::Private {
+                let ssh_private_key = ssh::ssh_private_key(&private_key, comment);
+                return Ok(Zeroizing::new(ssh_private_key.as_bytes().to_vec()));
+            }
+            private_key.public_key()
+        };
+
+        let exported = match self.format {
+            ExportFormat::Ssh => format!("{}\n", ssh::ssh_public_key(&public_key, comment)),
+            _ => {
+                let jwk = Jwk::from_ed25519_public_key(&public_key, self.kid.clone());
+                serde_json::to_string_pretty(&jwk)
+                    .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+            }
+        };
+        Ok(Zeroizing::new(exported.into_bytes()))
+    }
+}
+
+/// Exports a key with an encoding, as a [`KeyTypeVisitor`]
+struct ExportWith<'a> {
+    export_key: &'a ExportKey,
+    encoding: EncodingType,
+}
+
+impl KeyTypeVisitor for ExportWith<'_> {
+    type Output = Zeroizing<Vec<u8>>;
+
+    fn visit_private_key<Key>(&self) -> CliTypedResult<Self::Output>
+    where
+        Key: PrivateKey + ValidCryptoMaterial + KeyFormat,
+        Key::PublicKeyMaterial: ValidCryptoMaterial + KeyFormat,
+    {
+        self.export_key.export_of::<Key>(self.encoding)
+    }
+
+    fn visit_public_key<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+    ) -> CliTypedResult<Self::Output> {
+        self.export_key.export::<Key>(self.encoding)
+    }
+}
+
+/// Checks a private key file can be loaded as a key of a key type, without showing the key
+///
+/// Nothing is printed for a valid key, so it can be used in health checks, which only need the
+/// exit code.
+#[derive(Debug, Parser)]
+pub struct CheckKey {
+    /// Expected key type: `x25519`, `ed25519`, `secp256k1`, or `secp256r1`
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyType,
+    /// Private key file to check
+    #[clap(long, parse(from_os_str))]
+    key_file: PathBuf,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    passphrase_options: PassphraseOptions,
+}
+
+#[async_trait]
+impl CliCommand<()> for CheckKey {
+    fn command_name(&self) -> &'static str {
+        "CheckKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<()> {
+        check_key_file_valid(
+            &self.key_file,
+            self.encoding_options.encoding,
+            &self.encoding_options.bech32_hrp,
+            self.key_type,
+            &self.passphrase_options,
+        )
+    }
+}
+
+/// Checks a private key file holds a valid key of `key_type` in `encoding`, where a `bech32` key
+/// must have the human readable part `bech32_hrp`
+///
+/// The key is dropped as soon as it's loaded, which zeroizes it.  Encrypted files are decrypted
+/// with the `passphrase_options`.  Private keys of every key type are 32 bytes, so a key of
+/// another type is only caught when it's invalid for `key_type`.
+pub fn check_key_file_valid(
+    key_file: &Path,
+    encoding: EncodingType,
+    bech32_hrp: &str,
+    key_type: KeyType,
+    passphrase_options: &PassphraseOptions,
+) -> CliTypedResult<()> {
+    let check = CheckKeyFile {
+        encoding,
+        key_store: FileKeyStore::new(key_file.to_path_buf()),
+        passphrase_options,
+        bech32_hrp,
+    };
+    visit_key_type(&check, key_type, false)
+}
+
+/// Loads a key from a key store and drops it, as a [`KeyTypeVisitor`]
+struct CheckKeyFile<'a> {
+    encoding: EncodingType,
+    key_store: FileKeyStore,
+    passphrase_options: &'a PassphraseOptions,
+    bech32_hrp: &'a str,
+}
+
+impl CheckKeyFile<'_> {
+    fn check<Key: ValidCryptoMaterial + KeyFormat>(&self) -> CliTypedResult<()> {
+        self.encoding
+            .load_key_from_store::<Key>(
+                "--key-file",
+                &self.key_store,
+                self.passphrase_options,
+                self.bech32_hrp,
+            )
+            .map(drop)
+    }
+}
+
+impl KeyTypeVisitor for CheckKeyFile<'_> {
+    type Output = ();
+
+    fn visit_private_key<Key>(&self) -> CliTypedResult<()>
+    where
+        Key: PrivateKey + ValidCryptoMaterial + KeyFormat,
+        Key::PublicKeyMaterial: ValidCryptoMaterial + KeyFormat,
+    {
+        self.check::<Key>()
+    }
+
+    fn visit_public_key<Key: ValidCryptoMaterial + KeyFormat>(&self) -> CliTypedResult<()> {
+        self.check::<Key>()
+    }
+}
+
+/// Derives a child `ed25519` key of a master key file along a SLIP-0010 path, and saves it
+///
+/// The master key file holds the 64 byte extended key of `m`, its private key followed by its
+/// chain code, in the `--encoding`.  `ed25519` keys only have hardened children, so only
+/// hardened indices can be derived, and other key types can't be derived at all.
+#[derive(Debug, Parser)]
+pub struct DeriveKey {
+    /// Key type of the child key, only `ed25519` keys can be derived
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyType,
+    /// Extended master key file
+    ///
+    /// An encrypted master key is decrypted with the passphrase from `--passphrase-env` or
+    /// `--passphrase-file`, which also encrypts the child key with `--encrypt`, and is otherwise
+    /// prompted for.
+    #[clap(long, parse(from_os_str))]
+    master_key_file: PathBuf,
+    /// Index of the hardened child of the master key to derive, the same as `--path m/index'`
+    #[clap(long, required_unless_present = "path", conflicts_with = "path")]
+    index: Option<u32>,
+    /// SLIP-0010 path of the child key to derive, e.g. `m/44'/637'/0'/0'/0'`
+    #[clap(long)]
+    path: Option<DerivationPath>,
+    #[clap(flatten)]
+    save_params: SaveKey,
+}
+
+/// A derived key, as shown by `derive`
+#[derive(Debug, Serialize)]
+pub struct DerivedKey {
+    /// Path the key was derived at, e.g. `m/0'`
+    derivation_path: String,
+    /// Public key in hex
+    public_key: String,
+    #[serde(flatten)]
+    files: HashMap<&'static str, PathBuf>,
+    /// Keyring entry of the private key, with `--key-store keyring`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    private_key_keyring: Option<String>,
+}
+
+#[async_trait]
+impl CliCommand<DerivedKey> for DeriveKey {
+    fn command_name(&self) -> &'static str {
+        "DeriveKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<DerivedKey> {
+        if !matches!(self.key_type, KeyType::Ed25519) {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} keys can't be derived, SLIP-0010 derivation of hardened children is only \
+                supported for ed25519 keys",
+                self.key_type.name()
+            )));
+        }
+        let path = match self.index {
+            Some(index) => DerivationPath::hardened_child(index)?,
+            None => self.path.clone().unwrap_or_default(),
+        };
+
+        let key = mnemonic::derive_extended_key(&self.load_master_key()?, &path)?;
+        self.save_params.check_key_file()?;
+        let (public_key, files) = self.save_params.save_key(&key, self.key_type.name())?;
+        Ok(DerivedKey {
+            derivation_path: path.to_string(),
+            public_key: hex::encode(public_key.to_bytes()),
+            files,
+            private_key_keyring: self.save_params.keyring_location()?,
+        })
+    }
+}
+
+impl DeriveKey {
+    /// Loads the extended master key, decrypting it if it's encrypted
+    fn load_master_key(&self) -> CliTypedResult<Zeroizing<Vec<u8>>> {
+        let name = "--master-key-file";
+        let data = Zeroizing::new(read_from_file(&self.master_key_file)?);
+        let data = if encryption::is_encrypted(&data) {
+            let passphrase = self.save_params.passphrase_options.passphrase(&format!(
+                "Enter passphrase for {}",
+                self.master_key_file.display()
+            ))?;
+            Zeroizing::new(encryption::decrypt(name, &data, &passphrase)?)
+        } else {
+            data
+        };
+        Ok(Zeroizing::new(
+            self.save_params
+                .encoding_options
+                .encoding
+                .decode_bytes_with_hrp(
+                    name,
+                    data.to_vec(),
+                    &self.save_params.encoding_options.bech32_hrp,
+                )?,
+        ))
+    }
+}
+
+/// Rebuilds the public key file of a private key file, checking it against the existing one
+///
+/// This is `public-key --recover` for a private key file.  An existing public key file that
+/// doesn't match the private key may have been corrupted or tampered with, so both public keys
+/// are shown before it's replaced, which is prompted for unless using `--assume-yes`.  A
+/// matching public key file is left as is.
+#[derive(Debug, Parser)]
+pub struct RecoverKey {
+    /// Key type of the private key: `x25519`, `ed25519`, `secp256k1`, or `secp256r1`
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyType,
+    /// Private key file to rebuild the public key file of
+    #[clap(long, parse(from_os_str))]
+    key_file: PathBuf,
+    /// Public key file, defaults to the public key file of `key_file`, as from `generate`
+    #[clap(long, parse(from_os_str))]
+    pub_key_file: Option<PathBuf>,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+    #[clap(flatten)]
+    checksum_options: ChecksumOptions,
+    #[clap(flatten)]
+    passphrase_options: PassphraseOptions,
+    #[clap(flatten)]
+    prompt_options: PromptOptions,
+}
+
+#[async_trait]
+impl CliCommand<DerivedPublicKey> for RecoverKey {
+    fn command_name(&self) -> &'static str {
+        "RecoverKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<DerivedPublicKey> {
+        let output = match self.pub_key_file {
+            Some(pub_key_file) => pub_key_file,
+            None => public_key_file_of(&self.key_file)?,
+        };
+        DerivePublicKey {
+            io_options: IoOptions {
+                input: self.key_file,
+                output: Some(output),
+                print_private: false,
+            },
+            key_type: self.key_type,
+            recover: true,
+            encoding_options: self.encoding_options,
+            checksum_options: self.checksum_options,
+            passphrase_options: self.passphrase_options,
+            prompt_options: self.prompt_options,
+        }
+        .execute()
+        .await
+    }
+}
+
+/// Encrypts an existing private key file in place with a passphrase
+///
+/// The file is encrypted the same way as `generate --encrypt`, and the original is backed up to
+/// `key_file.bak` unless `--no-backup` is given, or to `key_file.bak-N` if there's already a
+/// backup.  Files that are already encrypted are refused.
+#[derive(Debug, Parser)]
+pub struct EncryptKey {
+    #[clap(flatten)]
+    key_file_options: RewriteKeyFileOptions,
+    #[clap(flatten)]
+    audit_log_options: AuditLogOptions,
+}
+
+#[async_trait]
+impl CliCommand<HashMap<&'static str, PathBuf>> for EncryptKey {
+    fn command_name(&self) -> &'static str {
+        "EncryptKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
+        let result = self.encrypt();
+        self.audit_log_options.record(
+            "encrypt",
+            None,
+            None,
+            Some(&self.key_file_options.key_file),
+            result.as_ref().err(),
+        );
+        result
+    }
+}
+
+impl EncryptKey {
+    fn encrypt(&self) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
+        let options = &self.key_file_options;
+        let data = options.read_key_file()?;
+        if encryption::is_encrypted(&data) {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} is already encrypted",
+                options.key_file.display()
+            )));
+        }
+        let passphrase = options.passphrase_options.new_passphrase()?;
+        options.rewrite_key_file(&data, |data| encryption::encrypt(data, &passphrase))
+    }
+}
+
+/// Decrypts an encrypted private key file in place, after prompting for its passphrase
+///
+/// The encrypted original is backed up to `key_file.bak` unless `--no-backup` is given, or to
+/// `key_file.bak-N` if there's already a backup.  Files that aren't encrypted are refused.
+#[derive(Debug, Parser)]
+pub struct DecryptKey {
+    #[clap(flatten)]
+    key_file_options: RewriteKeyFileOptions,
+}
+
+#[async_trait]
+impl CliCommand<HashMap<&'static str, PathBuf>> for DecryptKey {
+    fn command_name(&self) -> &'static str {
+        "DecryptKey"
+    }
+
+    async fn execute(self) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
+        let options = &self.key_file_options;
+        let data = options.read_key_file()?;
+        if !encryption::is_encrypted(&data) {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} isn't encrypted",
+                options.key_file.display()
+            )));
+        }
+        let passphrase = options.passphrase_options.passphrase(&format!(
+            "Enter passphrase for {}",
+            options.key_file.display()
+        ))?;
+        options.rewrite_key_file(&data, |data| {
+            encryption::decrypt("--key-file", data, &passphrase)
+        })
+    }
+}
+
+/// Extension of the backup of a key file rewritten by `encrypt` or `decrypt`
+pub const BACKUP_EXTENSION: &str = "bak";
+
+/// Options for rewriting a private key file in place
+#[derive(Debug, Parser)]
+pub struct RewriteKeyFileOptions {
+    /// Private key file to rewrite
+    #[clap(long, parse(from_os_str))]
+    pub key_file: PathBuf,
+    /// Don't back up the original file to `key_file.bak` before rewriting it
+    ///
+    /// An existing backup is never replaced, the next backup is `key_file.bak-2` and so on.
+    #[clap(long)]
+    pub no_backup: bool,
+    #[clap(flatten)]
+    pub passphrase_options: PassphraseOptions,
+}
+
+impl RewriteKeyFileOptions {
+    /// Reads the key file, zeroizing it once it's dropped
+    pub fn read_key_file(&self) -> CliTypedResult<Zeroizing<Vec<u8>>> {
+        Ok(Zeroizing::new(read_from_file(&self.key_file)?))
+    }
+
+    /// Backs up the original key file, and replaces it with `rewrite` of its contents
+    ///
+    /// The key file is replaced atomically, so it holds either the original or the rewritten key
+    /// even if the rewrite is interrupted.  Both files hold a private key, so both are only
+    /// readable by the user.
+    pub fn rewrite_key_file(
+        &self,
+        data: &[u8],
+        rewrite: impl FnOnce(&[u8]) -> CliTypedResult<Vec<u8>>,
+    ) -> CliTypedResult<HashMap<&'static str, PathBuf>> {
+        let rewritten = Zeroizing::new(rewrite(data)?);
+
+        let mut map = HashMap::new();
+        if !self.no_backup {
+            let backup_file = write_backup_file(&self.key_file, BACKUP_EXTENSION, data, 0o600)?;
+            map.insert("Backup Path", backup_file);
+        }
+        replace_file_atomically(&self.key_file, "private key", &rewritten, 0o600)?;
+        map.insert("PrivateKey Path", self.key_file.clone());
+        Ok(map)
+    }
+}
+
+/// An insertable option for giving a message to sign or verify
+#[derive(Debug, Parser)]
+pub struct MessageInputOptions {
+    /// Message input file name, the raw bytes of the file are the message
+    #[clap(long, group = "message_input", parse(from_os_str))]
+    message_file: Option<PathBuf>,
+    /// Message hex encoded e.g. 0xABCDE12345
+    #[clap(long, group = "message_input")]
+    message: Option<String>,
+    /// The message is the 64 byte SHA-512 digest of the message, to sign with Ed25519ph
+    #[clap(long)]
+    pub prehashed: bool,
+}
+
+/// Largest message file that can be signed or verified
+pub const MAX_MESSAGE_FILE_SIZE: u64 = 1 << 30;
+/// Size of the chunks message files are read in
+const MESSAGE_CHUNK_SIZE: usize = 64 * 1024;
+
+impl MessageInputOptions {
+    /// Retrieves the message bytes from the command line or the message file
+    ///
+    /// Pure `ed25519` hashes the message twice, once for the nonce and once for the challenge,
+    /// so the message can't be streamed and is held in memory.  Message files are read in
+    /// chunks into a buffer of the file's size, which is at most [`MAX_MESSAGE_FILE_SIZE`].
+    pub fn message(&self) -> CliTypedResult<Vec<u8>> {
+        if let Some(ref file) = self.message_file {
+            let message = read_message_file(file.as_path(), MAX_MESSAGE_FILE_SIZE)?;
+            if message.is_empty() {
+                return Err(CliError::CommandArgumentError(format!(
+                    "Message file {} is empty",
+                    file.display()
+                )));
+            }
+            Ok(message)
+        } else if let Some(ref message) = self.message {
+            hex::decode(message.strip_prefix("0x").unwrap_or(message))
+                .map_err(|err| CliError::UnableToParse("--message", err.to_string()))
+        } else {
+            Err(CliError::CommandArgumentError(
+                "One of ['--message', '--message-file'] must be used".to_string(),
+            ))
+        }
+    }
+}
+
+/// Reads a message file of at most `max_size` bytes in chunks
+///
+/// The size is checked before reading, and the read is bounded too, as files can grow while
+/// being read and pipes have no size.
+pub fn read_message_file(path: &Path, max_size: u64) -> CliTypedResult<Vec<u8>> {
+    let name = path.display().to_string();
+    debug!("Reading message from {}", name);
+    let too_large = || {
+        CliError::CommandArgumentError(format!(
+            "Message file {} is larger than the limit of {} bytes",
+            name, max_size
+        ))
+    };
+    let file = std::fs::File::open(path)
+        .map_err(|err| CliError::UnableToReadFile(name.clone(), err.to_string()))?;
+    let size = file
+        .metadata()
+        .map_err(|err| CliError::IO(name.clone(), err))?
+        .len();
+    if size > max_size {
+        return Err(too_large());
+    }
+
+    let mut reader = file.take(max_size + 1);
+    let mut message = Vec::with_capacity(size as usize);
+    let mut chunk = vec![0u8; MESSAGE_CHUNK_SIZE];
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => message.extend_from_slice(&chunk[..read]),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(CliError::IO(name.clone(), err)),
+        }
+    }
+    if message.len() as u64 > max_size {
+        return Err(too_large());
+    }
+    Ok(message)
+}
+
+/// Generates a `x25519`, `ed25519`, `secp256k1`, or `secp256r1` key.
+///
+/// This can be used for generating an identity.  Two files will be created
+/// `output_file` and `output_file.pub`.  `output_file` will contain the private
+/// key encoded with the `encoding` and `output_file.pub` will contain the public
+/// key encoded with the `encoding`.
+///
+/// A `x25519` or `ed25519` key can instead be derived from a BIP39 mnemonic, either an
+/// existing one given with `--mnemonic`, or a new one saved to `output_file.mnemonic` with
+/// `--with-mnemonic`.
+///
+/// `--seed` makes generation deterministic, which is only for tests.  Seeded keys are NOT safe
+/// for production use.
+///
+/// `--rng-from-file` seeds the RNG with entropy from another source, e.g. an HSM, instead of
+/// the OS.
+///
+/// `--vanity-prefix` generates `ed25519` keys until one has an account address starting with
+/// the prefix.
+///
+/// `--network-identity` also saves the peer identity of a `x25519` key to
+/// `output_file.identity.yaml`, for the network identity of a node config.
+///
+/// `--json-identity` shows a new `ed25519` key with its authentication key and account
+/// address in one object, for onboarding an account.
+///
+/// `--print-address` also shows the authentication key and account address of a new `ed25519`
+/// key, without a separate `auth-key` command.
+#[derive(Debug, Parser)]
+pub struct GenerateKey {
+    /// Key type: `x25519`, `ed25519`, `secp256k1`, or `secp256r1`
+    #[clap(long, default_value = "ed25519")]
+    key_type: KeyType,
+    /// BIP39 mnemonic phrase to derive the key from
+    ///
+    /// The same mnemonic always derives the same key.
+    #[clap(long, group = "key_source")]
+    mnemonic: Option<String>,
+    /// Generate a new 24 word BIP39 mnemonic and derive the key from it
+    ///
+    /// The mnemonic is saved to `output_file.mnemonic`, or printed to stderr with `--stdout`.
+    #[clap(long, group = "key_source")]
+    with_mnemonic: bool,
+    /// 32 byte hex seed for the RNG, so the same seed always generates the same key
+    ///
+    /// WARNING: a seeded key is only as secret as its seed.  Seeded keys are for reproducible
+    /// tests, and are NOT safe to use in production.
+    #[clap(long, group = "key_source")]
+    seed: Option<String>,
+    /// File of exactly 32 bytes to seed the RNG with, e.g. entropy exported from an HSM
+    ///
+    /// WARNING: the file MUST come from a cryptographically secure source, as the keys are
+    /// only as secret and as random as its contents.  The same file always generates the same
+    /// keys, so it should be used once and then destroyed.
+    #[clap(long, group = "key_source", parse(from_os_str))]
+    rng_from_file: Option<PathBuf>,
+    /// Generate `ed25519` keys until the account address starts with this hex prefix
+    ///
+    /// Keys are generated on every core, or on `--threads`.  Each hex digit makes the search 16
+    /// times slower, so long prefixes are exponentially slow, e.g. 6 digits takes around 16
+    /// million attempts.
+    #[clap(
+        long,
+        group = "key_source",
+        conflicts_with_all = &["derivation_path", "count", "multi", "regen_public"]
+    )]
+    vanity_prefix: Option<String>,
+    /// Private key in hex to import instead of generating one, saved with its public key
+    ///
+    /// WARNING: arguments can be seen by other users of the machine, e.g. in the process list,
+    /// and are kept in the shell history.
+    #[clap(
+        long,
+        group = "key_source",
+        conflicts_with_all = &["derivation_path", "count", "multi", "regen_public"]
+    )]
+    from_private_hex: Option<String>,
+    /// Private key in base 64 to import instead of generating one, like `--from-private-hex`
+    #[clap(
+        long,
+        group = "key_source",
+        conflicts_with_all = &["derivation_path", "count", "multi", "regen_public"]
+    )]
+    from_private_base64: Option<String>,
+    /// Number of keys to try for `--vanity-prefix` before giving up
+    #[clap(long, default_value = "100000000", requires = "vanity_prefix")]
+    vanity_max_attempts: u64,
+    /// Number of threads to generate `--count` or `--vanity-prefix` keys on, defaults to the
+    /// number of cores
+    ///
+    /// A batch is still saved in order.  A `--seed` or `--rng-from-file` batch is always
+    /// generated on one thread.
+    #[clap(long, conflicts_with_all = &["seed", "rng_from_file"])]
+    threads: Option<usize>,
+    /// SLIP-0010 path to derive the key at from the mnemonic, e.g. `m/44'/637'/0'/0'/0'`
+    ///
+    /// Only hardened indices are supported.  Defaults to the path of the first Aptos account
+    /// key, `m/44'/637'/0'/0'/0'`, as used by wallets.
+    #[clap(long)]
+    derivation_path: Option<DerivationPath>,
+    /// Output format: `default` for the saved file names and the public key, or `json` for an
+    /// object describing the key
+    #[clap(long, default_value = "default")]
+    output_format: OutputFormat,
+    /// Include the private key in hex in the `json` output
+    ///
+    /// The private key is never included otherwise.
+    #[clap(long)]
+    include_private_key: bool,
+    /// Also print the private key in hex to stdout with the `default` output
+    ///
+    /// Only for throwaway keys e.g. for development, as the private key ends up in the terminal
+    /// and any logs of it.  Without this, the `default` output never has the private key.
+    #[clap(
+        long,
+        conflicts_with_all = &["stdout", "quiet", "count", "multi", "json_identity"]
+    )]
+    print_private: bool,
+    /// Number of keys to generate, saved to `output_file-0`, `output_file-1`, ...
+    ///
+    /// Each key is drawn fresh, and the public keys of the batch are shown.  Progress is shown
+    /// on stderr when it's a terminal, unless using `--output-format json`.
+    #[clap(
+        long,
+        conflicts_with_all = &[
+            "mnemonic",
+            "with_mnemonic",
+            "derivation_path",
+            "stdout",
+            "pub_key_file",
+            "regen_public"
+        ]
+    )]
+    count: Option<usize>,
+    /// Also save a manifest of the `--count` keys, JSON if it ends in `.json`, CSV otherwise
+    ///
+    /// Each key is listed with its index, private key file, public key in hex, and the account
+    /// address of an `ed25519` key.  Private keys are never listed.
+    #[clap(long, parse(from_os_str), requires = "count")]
+    manifest_out: Option<PathBuf>,
+    /// Number of `ed25519` keys to generate for a multi-ed25519 key, saved like `--count`
+    ///
+    /// The combined public key of the keys and `--threshold` is saved to
+    /// `output_file.multi.pub`.
+    #[clap(
+        long,
+        requires = "threshold",
+        conflicts_with_all = &[
+            "mnemonic",
+            "with_mnemonic",
+            "derivation_path",
+            "stdout",
+            "count",
+            "pub_key_file",
+            "regen_public"
+        ]
+    )]
+    multi: Option<usize>,
+    /// Number of the `--multi` keys that must sign for a multi-ed25519 signature
+    #[clap(long, requires = "multi")]
+    threshold: Option<u8>,
+    /// Don't print anything on success, only save the files
+    ///
+    /// Errors are still printed.
+    #[clap(long, conflicts_with = "stdout")]
+    quiet: bool,
+    /// Also save a node config identity of a `x25519` key to `output_file.identity.yaml`
+    ///
+    /// It's a `from_file` identity, referring to an identity blob with the key in
+    /// `output_file.identity-blob.yaml`, which is readable only by the user.  With
+    /// `--inline-key` it's a `from_config` identity instead.
+    #[clap(
+        long,
+        conflicts_with_all = &["stdout", "count", "multi", "regen_public", "vanity_prefix"]
+    )]
+    network_identity: bool,
+    /// Put the private key in the `--network-identity` file, as a `from_config` identity,
+    /// rather than in an identity blob
+    ///
+    /// The identity file is then readable only by the user, like the private key file.
+    #[clap(long, requires = "network_identity")]
+    inline_key: bool,
+    /// Show the private key, public key, authentication key and account address of a new
+    /// `ed25519` key as one JSON object, instead of the saved files
+    ///
+    /// The key is still saved as usual.
+    #[clap(
+        long,
+        conflicts_with_all = &[
+            "stdout",
+            "count",
+            "multi",
+            "regen_public",
+            "vanity_prefix",
+            "mnemonic",
+            "with_mnemonic",
+            "from_private_hex",
+            "from_private_base64",
+            "include_private_key"
+        ]
+    )]
+    json_identity: bool,
+    /// Show the private key of `--json-identity` as `<redacted>`
+    #[clap(long, requires = "json_identity")]
+    redact_private: bool,
+    /// Also show the authentication key and account address of a new `ed25519` key
+    ///
+    /// They're printed after the key is saved, or included in the `json` output.  Other key
+    /// types have no account address, so only a warning is shown for them.
+    #[clap(long)]
+    print_address: bool,
+    /// Authentication scheme of the `--print-address` account: `single` for each key on its
+    /// own, or `multi` for the multi-ed25519 key of the `--multi` keys, defaults to `single`
+    ///
+    /// Only `ed25519` keys have these schemes, and `multi_agg` isn't supported.
+    #[clap(long)]
+    scheme: Option<AuthScheme>,
+    #[clap(flatten)]
+    save_params: SaveKey,
+    #[clap(flatten)]
+    audit_log_options: AuditLogOptions,
+}
+
+#[async_trait]
+impl CliCommand<GenerateKeyOutput> for GenerateKey {
+    fn command_name(&self) -> &'static str {
+        "GenerateKey"
+    }
+
+    fn json_errors(&self) -> bool {
+        self.output_format == OutputFormat::Json
+    }
+
+    async fn execute(self) -> CliTypedResult<GenerateKeyOutput> {
+        // Each saved key is logged as it's saved, so only a failure is left to log
+        let result = self.generate();
+        if let Err(ref err) = result {
+            self.audit_log_options.record(
+                "generate",
+                Some(self.key_type.name()),
+                None,
+                self.save_params.output_file.as_deref(),
+                Some(err),
+            );
+        }
+        result
+    }
+}
+
+impl GenerateKey {
+    fn generate(&self) -> CliTypedResult<GenerateKeyOutput> {
+        // Printed keys are the only output, so there's nowhere for the JSON to go
+        if self.output_format == OutputFormat::Json && self.save_params.prints_keys() {
+            return Err(CliError::CommandArgumentError(
+                "--output-format json can't be used with --stdout".to_string(),
+            ));
+        }
+        if self.include_private_key && self.output_format != OutputFormat::Json {
+            return Err(CliError::CommandArgumentError(
+                "--include-private-key can only be used with --output-format json".to_string(),
+            ));
+        }
+        if self.print_private {
+            if self.output_format != OutputFormat::Default {
+                return Err(CliError::CommandArgumentError(
+                    "--print-private can only be used with --output-format default, use \
+                    --include-private-key for json"
+                        .to_string(),
+                ));
+            }
+            eprintln!(
+                "WARNING: --print-private prints the private key to stdout, only use it for \
+                throwaway keys"
+            );
+        }
+        if self.network_identity {
+            self.check_network_identity()?;
+        }
+        self.check_scheme()?;
+        if self.print_address && !matches!(self.key_type, KeyType::Ed25519) {
+            eprintln!(
+                "Warning: {} keys have no account address, so --print-address is ignored",
+                self.key_type.name()
+            );
+        }
+        if let Some(count) = self.count {
+            return self.generate_batch(count);
+        }
+        if let (Some(count), Some(threshold)) = (self.multi, self.threshold) {
+            return self.generate_multi_ed25519(count, threshold);
+        }
+        if self.save_params.regen_public {
+            return self.regen_public_key();
+        }
+        self.save_params.check_key_file()?;
+        if self.with_mnemonic {
+            self.save_params.check_mnemonic_file()?;
+        }
+        if self.json_identity {
+            return self.generate_json_identity();
+        }
+        if let Some(ref prefix) = self.vanity_prefix {
+            return self.generate_vanity(prefix);
+        }
+        if let Some(private_key) = self.imported_private_key()? {
+            let generated_key = self.save_private_key(&self.save_params, &private_key)?;
+            return Ok(self.output(generated_key));
+        }
+
+        // ECDSA keys aren't derived from ed25519 keys, so can't come from a mnemonic
+        if matches!(self.key_type, KeyType::Secp256k1 | KeyType::Secp256r1)
+            && (self.mnemonic.is_some() || self.with_mnemonic)
+        {
+            return Err(CliError::CommandArgumentError(
+                "A mnemonic can only be used with x25519 or ed25519 keys".to_string(),
+            ));
+        }
+
+        if self.derivation_path.is_some() && self.mnemonic.is_none() && !self.with_mnemonic {
+            return Err(CliError::CommandArgumentError(
+                "--derivation-path can only be used with --mnemonic or --with-mnemonic".to_string(),
+            ));
+        }
+        let derivation_path = match self.derivation_path {
+            Some(ref path) => path.clone(),
+            None => DerivationPath::aptos(),
+        };
+
+        if self.mnemonic.is_none() && !self.with_mnemonic {
+            let (_, generated_key) = self.generate_new_key()?;
+            return Ok(self.output(generated_key));
+        }
+
+        // Derive the ed25519 key from a mnemonic
+        let (mnemonic, new_mnemonic) = match self.mnemonic {
+            Some(ref phrase) => (mnemonic::parse_mnemonic(phrase)?, false),
+            None => (mnemonic::generate_mnemonic()?, true),
+        };
+        let ed25519_key = mnemonic::derive_ed25519_key(&mnemonic, &derivation_path)?;
+
+        // The key is saved before its new mnemonic, so a mnemonic file is never left without
+        // the key it derives, if saving the key fails
+        let mut rng = self.rng()?;
+        let mut generated_key = self.convert_and_save(&self.save_params, ed25519_key, &mut rng)?;
+        if new_mnemonic {
+            generated_key.mnemonic_file = self.save_params.save_mnemonic(mnemonic.phrase())?;
+        }
+        Ok(self.output(generated_key))
+    }
+}
+
+/// Generates a key of `key_type` and saves it to `key_file` in `encoding`, with its public key
+/// file beside it
+///
+/// This is `generate --key-type <key_type> --output-file <key_file> --encoding <encoding>`,
+/// without parsing any arguments.  An existing file is only overwritten with `assume_yes`.
+pub fn generate_and_save(
+    key_type: KeyType,
+    encoding: EncodingType,
+    key_file: &Path,
+    assume_yes: bool,
+) -> CliTypedResult<GeneratedPrivateKey> {
+    let prompt_options = PromptOptions {
+        assume_yes,
+        assume_no: false,
+        no_overwrite: false,
+        prompt_timeout: None,
+    };
+    let command = GenerateKey::new(
+        key_type,
+        SaveKey::new(key_file.to_path_buf(), encoding, prompt_options),
+    );
+    command.save_params.check_key_file()?;
+    let (private_key, _) = command.generate_new_key()?;
+    Ok(private_key)
+}
+
+/// A generated private key of any key type
+pub enum GeneratedPrivateKey {
+    X25519(x25519::PrivateKey),
+    Ed25519(ed25519::Ed25519PrivateKey),
+    Secp256k1(secp256k1::Secp256k1PrivateKey),
+    Secp256r1(secp256r1::Secp256r1PrivateKey),
+}
+
+/// Description of a saved key, for keeping an inventory of keys
+#[derive(Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct KeyMetadata {
+    /// When the key was saved, in seconds since the Unix epoch
+    pub created_at_secs: u64,
+    pub key_type: String,
+    /// Encoding of the key files
+    pub encoding: String,
+    /// Public key in hex
+    pub public_key: String,
+}
+
+/// Format of the output of `generate`
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Names of the saved files
+    Default,
+    /// The saved files, public key, and key type as fields of an object
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(OutputFormat::Default),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err("Invalid output format"),
+        }
+    }
+}
+
+/// Output of `generate`, in the shape chosen by `--output-format`
+///
+/// A batch of keys is always described in full, so the public keys can be seen.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum GenerateKeyOutput {
+    Default(SavedKeyOutput),
+    Json(GeneratedKey),
+    Batch(Vec<GeneratedKey>),
+    Multi(GeneratedMultiKey),
+    Identity(JsonIdentity),
+}
+
+/// A saved key, as shown by `generate` with the `default` output format
+#[derive(Debug, Serialize)]
+pub struct SavedKeyOutput {
+    #[serde(flatten)]
+    paths: HashMap<&'static str, PathBuf>,
+    /// Keyring entry of the private key, with `--key-store keyring`
+    #[serde(rename = "PrivateKey Keyring", skip_serializing_if = "Option::is_none")]
+    private_key_keyring: Option<String>,
+    /// Public key in hex
+    #[serde(rename = "PublicKey")]
+    public_key: String,
+    /// Private key in hex, only with `--print-private`
+    #[serde(rename = "PrivateKey", skip_serializing_if = "Option::is_none")]
+    private_key: Option<String>,
+    /// Authentication key in hex, only with `--print-address`
+    #[serde(rename = "AuthenticationKey", skip_serializing_if = "Option::is_none")]
+    authentication_key: Option<String>,
+    /// Account address, only with `--print-address`
+    #[serde(rename = "AccountAddress", skip_serializing_if = "Option::is_none")]
+    account_address: Option<String>,
+}
+
+/// Shown in place of the private key of `--json-identity` with `--redact-private`
+pub const REDACTED: &str = "<redacted>";
+
+/// A new `ed25519` key and its account, as shown by `generate --json-identity`
+#[derive(Debug, Serialize)]
+pub struct JsonIdentity {
+    /// Private key in hex, or [`REDACTED`]
+    private_key: String,
+    /// Public key in hex
+    public_key: String,
+    #[serde(flatten)]
+    auth_key_info: AuthKeyInfo,
+}
+
+/// A generated key, as shown by `generate --output-format json`
+#[derive(Debug, Serialize)]
+pub struct GeneratedKey {
+    key_type: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    private_key_file: Option<PathBuf>,
+    /// Keyring entry of the private key, with `--key-store keyring`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    private_key_keyring: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    public_key_file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mnemonic_file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata_file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    algorithm_tag_file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checksum_file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    identity_file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    identity_blob_file: Option<PathBuf>,
+    /// Public key in hex
+    public_key: String,
+    /// Private key in hex, only with `--include-private-key` or `--print-private`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    private_key: Option<String>,
+    /// Authentication key and account address, only with `--print-address`
+    #[serde(flatten)]
+    account: Option<AuthKeyInfo>,
+}
+
+/// The keys of a multi-ed25519 key, and their combined public key
+#[derive(Debug, Serialize)]
+pub struct GeneratedMultiKey {
+    threshold: u8,
+    public_key_file: PathBuf,
+    /// Combined public key in hex, the public keys followed by the threshold
+    public_key: String,
+    /// Authentication key and account address of the combined public key, only with
+    /// `--print-address --scheme multi`
+    #[serde(flatten)]
+    account: Option<AuthKeyInfo>,
+    keys: Vec<GeneratedKey>,
+}
+
+impl GeneratedKey {
+    /// Names of the saved files, as shown by default
+    fn into_paths(self) -> HashMap<&'static str, PathBuf> {
+        let mut map = HashMap::new();
+        if let Some(path) = self.private_key_file {
+            map.insert("PrivateKey Path", path);
+        }
+        if let Some(path) = self.public_key_file {
+            map.insert("PublicKey Path", path);
+        }
+        if let Some(path) = self.mnemonic_file {
+            map.insert("Mnemonic Path", path);
+        }
+        if let Some(path) = self.metadata_file {
+            map.insert("Metadata Path", path);
+        }
+        if let Some(path) = self.algorithm_tag_file {
+            map.insert("Algorithm Tag Path", path);
+        }
+        if let Some(path) = self.checksum_file {
+            map.insert("Checksum Path", path);
+        }
+        if let Some(path) = self.identity_file {
+            map.insert("Identity Path", path);
+        }
+        if let Some(path) = self.identity_blob_file {
+            map.insert("Identity Blob Path", path);
+        }
+        map
+    }
+
+    /// Names of the saved files and the public key, as shown by default, with the private key
+    /// only if it was asked for
+    fn into_saved_output(mut self) -> SavedKeyOutput {
+        let public_key = std::mem::take(&mut self.public_key);
+        let private_key = self.private_key.take();
+        let private_key_keyring = self.private_key_keyring.take();
+        let (authentication_key, account_address) = match self.account.take() {
+            Some(account) => (
+                Some(account.authentication_key),
+                Some(account.account_address.to_hex_literal()),
+            ),
+            None => (None, None),
+        };
+        SavedKeyOutput {
+            paths: self.into_paths(),
+            private_key_keyring,
+            public_key,
+            private_key,
+            authentication_key,
+            account_address,
+        }
+    }
+}
+
+/// A key of a batch, as listed in the `--manifest-out` of `generate --count`
+///
+/// The manifest describes the keys without their private keys, so it can be shared.
+#[derive(Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct BatchManifestEntry {
+    /// Index of the key in the batch, from 0
+    pub index: usize,
+    /// Private key file of the key
+    pub file: PathBuf,
+    /// Public key in hex
+    pub public_key: String,
+    /// Account address of an `ed25519` key, which other key types don't have
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_address: Option<AccountAddress>,
+}
+
+/// Header of a CSV `--manifest-out`
+pub const BATCH_MANIFEST_CSV_HEADER: &str = "index,file,public_key,account_address";
+
+impl BatchManifestEntry {
+    fn of(
+        index: usize,
+        save_params: &SaveKey,
+        private_key: &GeneratedPrivateKey,
+    ) -> CliTypedResult<BatchManifestEntry> {
+        let (public_key, account_address) = match private_key {
+            GeneratedPrivateKey::X25519(key) => (hex::encode(key.public_key().to_bytes()), None),
+            GeneratedPrivateKey::Ed25519(key) => (
+                hex::encode(key.public_key().to_bytes()),
+                Some(AuthKeyInfo::of(&key.public_key()).account_address),
+            ),
+            GeneratedPrivateKey::Secp256k1(key) => (hex::encode(key.public_key().to_bytes()), None),
+            GeneratedPrivateKey::Secp256r1(key) => (hex::encode(key.public_key().to_bytes()), None),
+        };
+        Ok(BatchManifestEntry {
+            index,
+            file: save_params.output_file()?.to_path_buf(),
+            public_key,
+            account_address,
+        })
+    }
+
+    /// The entry as a CSV line, without a newline
+    fn to_csv(&self) -> String {
+        [
+            self.index.to_string(),
+            self.file.display().to_string(),
+            self.public_key.clone(),
+            self.account_address
+                .map(|address| address.to_hex_literal())
+                .unwrap_or_default(),
+        ]
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<_>>()
+        .join(",")
+    }
+}
+
+/// Quotes a CSV field if it holds a comma, quote, or line break, doubling its quotes
+fn csv_field(field: &str) -> String {
+    if field.contains(|c: char| matches!(c, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Saves the manifest of a batch, as JSON if the file ends in `.json` and as CSV otherwise
+fn save_batch_manifest(path: &Path, entries: &[BatchManifestEntry]) -> CliTypedResult<()> {
+    let manifest = if path.extension() == Some(OsStr::new("json")) {
+        serde_json::to_string_pretty(entries)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+    } else {
+        let mut lines = vec![BATCH_MANIFEST_CSV_HEADER.to_string()];
+        lines.extend(entries.iter().map(BatchManifestEntry::to_csv));
+        lines.join("\n") + "\n"
+    };
+    write_to_public_file(path, "manifest", manifest.as_bytes())
+}
+
+/// Number of keys between progress lines of a batch
+pub const BATCH_PROGRESS_INTERVAL: usize = 100;
+
+/// Progress of generating a batch of keys, written every [`BATCH_PROGRESS_INTERVAL`] keys
+///
+/// Nothing is written without a writer.
+pub struct BatchProgress<W: Write> {
+    writer: Option<W>,
+    total: usize,
+    done: usize,
+    start: Instant,
+}
+
+impl<W: Write> BatchProgress<W> {
+    pub fn new(total: usize, writer: Option<W>) -> BatchProgress<W> {
+        BatchProgress {
+            writer,
+            total,
+            done: 0,
+            start: Instant::now(),
+        }
+    }
+
+    /// Counts a generated key
+    pub fn tick(&mut self) {
+        self.done += 1;
+        if self.done % BATCH_PROGRESS_INTERVAL == 0 && self.done < self.total {
+            let line = format!("Generated {}/{} keys", self.done, self.total);
+            self.write_line(&line);
+        }
+    }
+
+    /// Writes the summary of the batch
+    pub fn finish(&mut self) {
+        let line = format!(
+            "Generated {} keys in {:.2}s",
+            self.done,
+            self.start.elapsed().as_secs_f64()
+        );
+        self.write_line(&line);
+    }
+
+    /// Progress is best effort, so failing to write it doesn't fail the batch
+    fn write_line(&mut self, line: &str) {
+        if let Some(writer) = self.writer.as_mut() {
+            let _ = writeln!(writer, "{}", line);
+        }
+    }
+}
+
+impl GenerateKey {
+    /// Parameters for generating a single key of `key_type` from the OS RNG, as without any
+    /// other option
+    fn new(key_type: KeyType, save_params: SaveKey) -> GenerateKey {
+        GenerateKey {
+            key_type,
+            mnemonic: None,
+            with_mnemonic: false,
+            seed: None,
+            rng_from_file: None,
+            vanity_prefix: None,
+            from_private_hex: None,
+            from_private_base64: None,
+            vanity_max_attempts: 100_000_000,
+            threads: None,
+            derivation_path: None,
+            output_format: OutputFormat::Default,
+            include_private_key: false,
+            print_private: false,
+            count: None,
+            manifest_out: None,
+            multi: None,
+            threshold: None,
+            quiet: false,
+            network_identity: false,
+            inline_key: false,
+            json_identity: false,
+            redact_private: false,
+            print_address: false,
+            scheme: None,
+            save_params,
+            audit_log_options: AuditLogOptions::default(),
+        }
+    }
+
+    /// The private key given with `--from-private-hex` or `--from-private-base64`, if any
+    fn imported_private_key(&self) -> CliTypedResult<Option<GeneratedPrivateKey>> {
+        let (name, encoding, text) = match (&self.from_private_hex, &self.from_private_base64) {
+            (Some(hex), _) => ("--from-private-hex", EncodingType::Hex, hex),
+            (_, Some(base64)) => ("--from-private-base64", EncodingType::Base64, base64),
+            (None, None) => return Ok(None),
+        };
+        let bytes = Zeroizing::new(encoding.decode_bytes(name, text.as_bytes().to_vec())?);
+        if bytes.len() != IMPORTED_PRIVATE_KEY_LENGTH {
+            return Err(CliError::CommandArgumentError(format!(
+                "{} must be a {} byte {} private key, got {} bytes",
+                name,
+                IMPORTED_PRIVATE_KEY_LENGTH,
+                self.key_type.name(),
+                bytes.len()
+            )));
+        }
+
+        let invalid_key = |err: aptos_crypto::CryptoMaterialError| {
+            CliError::CommandArgumentError(format!(
+                "{} isn't a valid {} private key: {}",
+                name,
+                self.key_type.name(),
+                err
+            ))
+        };
+        let private_key = match self.key_type {
+            KeyType::X25519 => GeneratedPrivateKey::X25519(
+                x25519::PrivateKey::try_from(bytes.as_slice()).map_err(invalid_key)?,
+            ),
+            KeyType::Ed25519 => GeneratedPrivateKey::Ed25519(
+                ed25519::Ed25519PrivateKey::try_from(bytes.as_slice()).map_err(invalid_key)?,
+            ),
+            KeyType::Secp256k1 => GeneratedPrivateKey::Secp256k1(
+                secp256k1::Secp256k1PrivateKey::try_from(bytes.as_slice()).map_err(invalid_key)?,
+            ),
+            KeyType::Secp256r1 => GeneratedPrivateKey::Secp256r1(
+                secp256r1::Secp256r1PrivateKey::try_from(bytes.as_slice()).map_err(invalid_key)?,
+            ),
+        };
+        Ok(Some(private_key))
+    }
+
+    /// Generates a key from the RNG, and saves it
+    fn generate_new_key(&self) -> CliTypedResult<(GeneratedPrivateKey, GeneratedKey)> {
+        let mut rng = self.rng()?;
+        let ed25519_key = ed25519::Ed25519PrivateKey::generate(&mut rng);
+        let private_key = self.convert_key(ed25519_key, &mut rng)?;
+        let generated_key = self.save_private_key(&self.save_params, &private_key)?;
+        Ok((private_key, generated_key))
+    }
+
+    /// Generates and saves an `ed25519` key, and describes it as an account identity
+    fn generate_json_identity(&self) -> CliTypedResult<GenerateKeyOutput> {
+        if !matches!(self.key_type, KeyType::Ed25519) {
+            return Err(CliError::CommandArgumentError(format!(
+                "--json-identity needs an ed25519 key, not {}",
+                self.key_type
+            )));
+        }
+        let private_key = match self.generate_new_key()? {
+            (GeneratedPrivateKey::Ed25519(private_key), _) => private_key,
+            _ => {
+                return Err(CliError::UnexpectedError(
+                    "Generated key isn't an ed25519 key".to_string(),
+                ))
+            }
+        };
+        let public_key = private_key.public_key();
+        Ok(GenerateKeyOutput::Identity(JsonIdentity {
+            private_key: if self.redact_private {
+                REDACTED.to_string()
+            } else {
+                hex::encode(private_key.to_bytes())
+            },
+            public_key: hex::encode(public_key.to_bytes()),
+            auth_key_info: AuthKeyInfo::of(&public_key),
+        }))
+    }
+
+    /// Generates `count` keys, saved to `output_file-0`, `output_file-1`, ...
+    fn generate_batch(&self, count: usize) -> CliTypedResult<GenerateKeyOutput> {
+        if count == 0 {
+            return Err(CliError::CommandArgumentError(
+                "--count must be at least 1".to_string(),
+            ));
+        }
+        let batch = self.batch_save_params(count)?;
+        if let Some(ref manifest_out) = self.manifest_out {
+            self.save_params.check_file(manifest_out)?;
+        }
+
+        // Progress goes to stderr, and only when it's watched and nothing is parsing the output
+        let show_progress =
+            self.output_format != OutputFormat::Json && atty::is(atty::Stream::Stderr);
+        let mut progress = BatchProgress::new(count, show_progress.then(std::io::stderr));
+
+        let private_keys = self.generate_batch_keys(count)?;
+        let generated_keys = batch
+            .iter()
+            .zip(private_keys.iter())
+            .map(|(save_params, private_key)| {
+                let generated_key = self.save_private_key(save_params, private_key)?;
+                progress.tick();
+                Ok(generated_key)
+            })
+            .collect::<CliTypedResult<Vec<_>>>()?;
+        progress.finish();
+
+        if let (Some(manifest_out), false) = (&self.manifest_out, self.save_params.dry_run) {
+            let entries = batch
+                .iter()
+                .zip(private_keys.iter())
+                .enumerate()
+                .map(|(index, (save_params, private_key))| {
+                    BatchManifestEntry::of(index, save_params, private_key)
+                })
+                .collect::<CliTypedResult<Vec<_>>>()?;
+            save_batch_manifest(manifest_out, &entries)?;
+        }
+        Ok(GenerateKeyOutput::Batch(generated_keys))
+    }
+
+    /// Generates the keys of a batch in order, spread across `--threads` threads
+    ///
+    /// Each thread draws keys from its own RNG seeded from the OS, so the keys of different
+    /// threads aren't correlated.  A seeded batch is generated on one thread from the seeded
+    /// RNG, so it's always the same.
+    fn generate_batch_keys(&self, count: usize) -> CliTypedResult<Vec<GeneratedPrivateKey>> {
+        if self.is_seeded() {
+            let mut rng = self.rng()?;
+            return (0..count)
+                .map(|_| {
+                    let ed25519_key = ed25519::Ed25519PrivateKey::generate(&mut rng);
+                    self.convert_key(ed25519_key, &mut rng)
+                })
+                .collect();
+        }
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.threads()?)
+            .build()
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        pool.install(|| {
+            (0..count)
+                .into_par_iter()
+                .map_init(StdRng::from_entropy, |rng, _| {
+                    let ed25519_key = ed25519::Ed25519PrivateKey::generate(rng);
+                    self.convert_key(ed25519_key, rng)
+                })
+                .collect()
+        })
+    }
+
+    /// Authentication scheme of the `--print-address` account
+    fn scheme(&self) -> AuthScheme {
+        self.scheme.unwrap_or(AuthScheme::Single)
+    }
+
+    /// Checks a `--scheme` is for the `--print-address` account of `ed25519` keys, and that
+    /// `multi` is for a multi-ed25519 key of several keys
+    fn check_scheme(&self) -> CliTypedResult<()> {
+        let scheme = match self.scheme {
+            Some(scheme) => scheme,
+            None => return Ok(()),
+        };
+        if !self.print_address {
+            return Err(CliError::CommandArgumentError(
+                "--scheme only changes the account of --print-address".to_string(),
+            ));
+        }
+        if !matches!(self.key_type, KeyType::Ed25519) {
+            return Err(CliError::CommandArgumentError(format!(
+                "--scheme is only for ed25519 keys, {} keys have no account address",
+                self.key_type.name()
+            )));
+        }
+        match (scheme, self.multi) {
+            (AuthScheme::Single, _) => Ok(()),
+            (AuthScheme::Multi, Some(count)) if count >= 2 => Ok(()),
+            (AuthScheme::Multi, _) => Err(CliError::CommandArgumentError(
+                "--scheme multi needs a multi-ed25519 key of at least 2 keys, use --multi"
+                    .to_string(),
+            )),
+            (AuthScheme::MultiAgg, _) => Err(CliError::CommandArgumentError(
+                "--scheme multi_agg isn't supported, only the single and multi schemes of \
+                ed25519 and multi-ed25519 keys are"
+                    .to_string(),
+            )),
+        }
+    }
+
+    /// Generates `count` `ed25519` keys saved as a batch, and their multi-ed25519 public key
+    fn generate_multi_ed25519(
+        &self,
+        count: usize,
+        threshold: u8,
+    ) -> CliTypedResult<GenerateKeyOutput> {
+        if !matches!(self.key_type, KeyType::Ed25519) {
+            return Err(CliError::CommandArgumentError(format!(
+                "--multi only generates ed25519 keys, not {} keys",
+                self.key_type.name()
+            )));
+        }
+        if threshold == 0 || threshold as usize > count || count > MAX_MULTI_ED25519_KEYS {
+            return Err(CliError::CommandArgumentError(format!(
+                "--threshold and --multi must be 1 <= threshold <= multi <= {}, got {} of {}",
+                MAX_MULTI_ED25519_KEYS, threshold, count
+            )));
+        }
+        let batch = self.batch_save_params(count)?;
+        let public_key_file = append_file_extension(
+            self.save_params.output_file()?,
+            MULTI_ED25519_PUBLIC_KEY_EXTENSION,
+        )?;
+        self.save_params.check_file(&public_key_file)?;
+
+        let mut rng = self.rng()?;
+        let mut public_keys = Vec::with_capacity(count);
+        let mut keys = Vec::with_capacity(count);
+        for save_params in &batch {
+            let key = ed25519::Ed25519PrivateKey::generate(&mut rng);
+            public_keys.push(key.public_key());
+            keys.push(self.save_key(save_params, &key)?);
+        }
+
+        let public_key = MultiEd25519PublicKey::new(public_keys, threshold)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        let multi_key_file = self.save_params.key_file_at(&public_key_file);
+        if !self.save_params.dry_run {
+            multi_key_file.save("multi-ed25519 public key", &public_key)?;
+        } else {
+            // Still encoded, so a dry run fails like a real one for encodings that can't be used
+            multi_key_file.encode("multi-ed25519 public key", &public_key)?;
+        }
+
+        Ok(GenerateKeyOutput::Multi(GeneratedMultiKey {
+            threshold,
+            public_key_file,
+            public_key: hex::encode(public_key.to_bytes()),
+            account: if self.print_address && self.scheme() == AuthScheme::Multi {
+                Some(AuthKeyInfo::of_multi(&public_key))
+            } else {
+                None
+            },
+            keys,
+        }))
+    }
+
+    /// Generates an `ed25519` key whose account address starts with `prefix`
+    fn generate_vanity(&self, prefix: &str) -> CliTypedResult<GenerateKeyOutput> {
+        if !matches!(self.key_type, KeyType::Ed25519) {
+            return Err(CliError::CommandArgumentError(format!(
+                "--vanity-prefix only generates ed25519 keys, as only they have an account \
+                address, not {} keys",
+                self.key_type.name()
+            )));
+        }
+        let prefix = vanity::parse_vanity_prefix(prefix)?;
+        let vanity_key =
+            vanity::grind_vanity_key(&prefix, self.vanity_max_attempts, self.threads()?)?;
+        if self.output_format != OutputFormat::Json {
+            eprintln!(
+                "Found account address {} after {} attempts ({:.0} attempts/s)",
+                vanity_key.account_address.to_hex_literal(),
+                vanity_key.attempts,
+                vanity_key.attempts_per_sec()
+            );
+        }
+        let generated_key = self.save_key(&self.save_params, &vanity_key.private_key)?;
+        Ok(self.output(generated_key))
+    }
+
+    /// Regenerates the public key file of the existing private key file, for `--regen-public`
+    fn regen_public_key(&self) -> CliTypedResult<GenerateKeyOutput> {
+        if self.mnemonic.is_some()
+            || self.with_mnemonic
+            || self.is_seeded()
+            || self.derivation_path.is_some()
+        {
+            return Err(CliError::CommandArgumentError(
+                "--regen-public uses the existing private key, so no other key source can be given"
+                    .to_string(),
+            ));
+        }
+        self.save_params.check_public_key_file()?;
+
+        let generated_key = match self.key_type {
+            KeyType::X25519 => self.regen_public_key_of::<x25519::PrivateKey>()?,
+            KeyType::Ed25519 => self.regen_public_key_of::<ed25519::Ed25519PrivateKey>()?,
+            KeyType::Secp256k1 => self.regen_public_key_of::<secp256k1::Secp256k1PrivateKey>()?,
+            KeyType::Secp256r1 => self.regen_public_key_of::<secp256r1::Secp256r1PrivateKey>()?,
+        };
+        Ok(self.output(generated_key))
+    }
+
+    /// Loads the existing private key as a `Key`, and saves its public key
+    fn regen_public_key_of<Key>(&self) -> CliTypedResult<GeneratedKey>
+    where
+        Key: PrivateKey + ValidCryptoMaterial + KeyFormat,
+        Key::PublicKeyMaterial: KeyFormat,
+    {
+        let key_name = self.key_type.name();
+        let key: Key = self.save_params.load_private_key()?;
+        let public_key = key.public_key();
+        let public_key_file = self.save_params.save_public_key(&public_key, key_name)?;
+        Ok(self.describe_key(
+            &key,
+            &public_key,
+            Some(self.save_params.output_file()?.to_path_buf()),
+            Some(public_key_file),
+        ))
+    }
+
+    /// Output of a single key, in the `--output-format`
+    ///
+    /// A dry run always describes the key, so the public key can be seen.
+    fn output(&self, generated_key: GeneratedKey) -> GenerateKeyOutput {
+        if self.save_params.dry_run {
+            return GenerateKeyOutput::Json(generated_key);
+        }
+        match self.output_format {
+            OutputFormat::Default => GenerateKeyOutput::Default(generated_key.into_saved_output()),
+            OutputFormat::Json => GenerateKeyOutput::Json(generated_key),
+        }
+    }
+
+    /// Save parameters of each key of a batch, after checking none of their files exist
+    ///
+    /// Every file is checked before any key is saved, so a batch is never partially saved
+    /// because of an existing file.
+    fn batch_save_params(&self, count: usize) -> CliTypedResult<Vec<SaveKey>> {
+        let batch = (0..count)
+            .map(|index| self.save_params.for_index(index))
+            .collect::<CliTypedResult<Vec<_>>>()?;
+        for save_params in &batch {
+            save_params.check_key_file()?;
+        }
+        Ok(batch)
+    }
+
+    /// Whether the RNG is seeded by `--seed` or `--rng-from-file`, rather than from the OS
+    fn is_seeded(&self) -> bool {
+        self.seed.is_some() || self.rng_from_file.is_some()
+    }
+
+    /// RNG for generating keys, seeded by `--seed` or `--rng-from-file` if either is given
+    fn rng(&self) -> CliTypedResult<StdRng> {
+        if let Some(ref path) = self.rng_from_file {
+            return Ok(StdRng::from_seed(*read_rng_seed_file(path)?));
+        }
+        let seed = match self.seed {
+            Some(ref seed) => seed,
+            None => return Ok(StdRng::from_entropy()),
+        };
+
+        let seed = hex::decode(seed.trim().strip_prefix("0x").unwrap_or(seed.trim()))
+            .map_err(|err| CliError::CommandArgumentError(format!("Invalid --seed: {}", err)))?;
+        let seed: [u8; 32] = seed.as_slice().try_into().map_err(|_| {
+            CliError::CommandArgumentError(format!(
+                "--seed must be exactly 32 bytes, got {} bytes",
+                seed.len()
+            ))
+        })?;
+        Ok(StdRng::from_seed(seed))
+    }
+
+    /// Converts a generated `ed25519` key to the key type, and saves it
+    ///
+    /// `secp256k1` and `secp256r1` keys aren't converted, and are drawn from `rng` instead.
+    fn convert_and_save(
+        &self,
+        save_params: &SaveKey,
+        ed25519_key: ed25519::Ed25519PrivateKey,
+        rng: &mut StdRng,
+    ) -> CliTypedResult<GeneratedKey> {
+        let private_key = self.convert_key(ed25519_key, rng)?;
+        self.save_private_key(save_params, &private_key)
+    }
+
+    /// Converts a generated `ed25519` key to the key type
+    ///
+    /// `secp256k1` and `secp256r1` keys aren't converted, but drawn from `rng`.
+    fn convert_key(
+        &self,
+        ed25519_key: ed25519::Ed25519PrivateKey,
+        rng: &mut StdRng,
+    ) -> CliTypedResult<GeneratedPrivateKey> {
+        match self.key_type {
+            KeyType::X25519 => {
+                let private_key =
+                    x25519::PrivateKey::from_ed25519_private_bytes(&ed25519_key.to_bytes())
+                        .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+                verify_x25519_conversion(
+                    &ed25519_key,
+                    &private_key,
+                    self.save_params.encoding_options.encoding,
+                )?;
+                Ok(GeneratedPrivateKey::X25519(private_key))
+            }
+            KeyType::Ed25519 => Ok(GeneratedPrivateKey::Ed25519(ed25519_key)),
+            KeyType::Secp256k1 => Ok(GeneratedPrivateKey::Secp256k1(
+                secp256k1::Secp256k1PrivateKey::generate(rng),
+            )),
+            KeyType::Secp256r1 => Ok(GeneratedPrivateKey::Secp256r1(
+                secp256r1::Secp256r1PrivateKey::generate(rng),
+            )),
+        }
+    }
+
+    /// Saves a generated key of any key type, with its identity for `--network-identity`
+    fn save_private_key(
+        &self,
+        save_params: &SaveKey,
+        private_key: &GeneratedPrivateKey,
+    ) -> CliTypedResult<GeneratedKey> {
+        match private_key {
+            GeneratedPrivateKey::X25519(key) => {
+                let mut generated_key = self.save_key(save_params, key)?;
+                if self.network_identity {
+                    let (identity_file, identity_blob_file) =
+                        self.save_network_identity(save_params, key)?;
+                    generated_key.identity_file = Some(identity_file);
+                    generated_key.identity_blob_file = identity_blob_file;
+                }
+                Ok(generated_key)
+            }
+            GeneratedPrivateKey::Ed25519(key) => self.save_key(save_params, key),
+            GeneratedPrivateKey::Secp256k1(key) => self.save_key(save_params, key),
+            GeneratedPrivateKey::Secp256r1(key) => self.save_key(save_params, key),
+        }
+    }
+
+    /// Checks a `--network-identity` can be saved, before any key is generated
+    fn check_network_identity(&self) -> CliTypedResult<()> {
+        if !matches!(self.key_type, KeyType::X25519) {
+            return Err(CliError::CommandArgumentError(format!(
+                "--network-identity needs a x25519 key, not {}",
+                self.key_type
+            )));
+        }
+        // The identity needs the private key in a file, which a keyring would otherwise keep
+        if !self.inline_key
+            && !matches!(
+                self.save_params.key_store_options.key_store,
+                KeyStoreType::File
+            )
+        {
+            return Err(CliError::CommandArgumentError(
+                "--network-identity needs --key-store file, or --inline-key".to_string(),
+            ));
+        }
+        let identity_file = self.save_params.identity_file()?;
+        self.save_params.check_parent_dir(&identity_file)?;
+        self.save_params.check_file(&identity_file)?;
+        if !self.inline_key {
+            self.save_params
+                .check_file(&self.save_params.identity_blob_file()?)?;
+        }
+        Ok(())
+    }
+
+    /// Saves the node config identity of a `x25519` key, with its peer id derived from its
+    /// public key, and the identity blob it refers to unless the key is inlined with
+    /// `--inline-key`
+    ///
+    /// Returns the identity file, and the identity blob file if there is one.
+    fn save_network_identity(
+        &self,
+        save_params: &SaveKey,
+        key: &x25519::PrivateKey,
+    ) -> CliTypedResult<(PathBuf, Option<PathBuf>)> {
+        let key_bytes = Zeroizing::new(key.to_bytes());
+        let network_key = x25519::PrivateKey::try_from(key_bytes.as_slice())
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+        let identity_file = save_params.identity_file()?;
+        if self.inline_key {
+            let peer_id = from_identity_public_key(key.public_key());
+            let yaml = Zeroizing::new(serde_yaml::to_string(&Identity::from_config(
+                network_key,
+                peer_id,
+            ))?);
+            if !save_params.dry_run {
+                write_to_user_only_file(&identity_file, "network identity", yaml.as_bytes())?;
+            }
+            return Ok((identity_file, None));
+        }
+
+        // The peer id of a blob without an account address is derived from its key
+        let identity_blob_file = save_params.identity_blob_file()?;
+        let blob = IdentityBlob {
+            account_address: None,
+            account_key: None,
+            consensus_key: None,
+            network_key,
+        };
+        let blob_yaml = Zeroizing::new(serde_yaml::to_string(&blob)?);
+        let yaml = serde_yaml::to_string(&Identity::from_file(identity_blob_file.clone()))?;
+        if !save_params.dry_run {
+            write_to_user_only_file(
+                &identity_blob_file,
+                "network identity blob",
+                blob_yaml.as_bytes(),
+            )?;
+            write_to_public_file(&identity_file, "network identity", yaml.as_bytes())?;
+        }
+        Ok((identity_file, Some(identity_blob_file)))
+    }
+
+    /// Number of threads to generate keys on, every core unless `--threads` is given
+    fn threads(&self) -> CliTypedResult<usize> {
+        match self.threads {
+            Some(0) => Err(CliError::CommandArgumentError(
+                "--threads must be at least 1".to_string(),
+            )),
+            Some(threads) => Ok(threads),
+            None => Ok(std::thread::available_parallelism()
+                .map(|threads| threads.get())
+                .unwrap_or(1)),
+        }
+    }
+
+    /// Saves the key, and describes it
+    fn save_key<Key>(&self, save_params: &SaveKey, key: &Key) -> CliTypedResult<GeneratedKey>
+    where
+        Key: PrivateKey + ValidCryptoMaterial + KeyFormat,
+        Key::PublicKeyMaterial: KeyFormat,
+    {
+        let (public_key, mut map) = save_params.save_key(key, self.key_type.name())?;
+        if !save_params.dry_run {
+            self.audit_log_options.record(
+                "generate",
+                Some(self.key_type.name()),
+                Some(hex::encode(public_key.to_bytes())),
+                map.get("PrivateKey Path").map(PathBuf::as_path),
+                None,
+            );
+        }
+        let mut generated_key = self.describe_key(
+            key,
+            &public_key,
+            map.remove("PrivateKey Path"),
+            map.remove("PublicKey Path"),
+        );
+        generated_key.metadata_file = map.remove("Metadata Path");
+        generated_key.algorithm_tag_file = map.remove("Algorithm Tag Path");
+        generated_key.checksum_file = map.remove("Checksum Path");
+        generated_key.private_key_keyring = save_params.keyring_location()?;
+        Ok(generated_key)
+    }
+
+    /// Describes a saved key and its public key
+    fn describe_key<Key: PrivateKey + ValidCryptoMaterial>(
+        &self,
+        key: &Key,
+        public_key: &Key::PublicKeyMaterial,
+        private_key_file: Option<PathBuf>,
+        public_key_file: Option<PathBuf>,
+    ) -> GeneratedKey {
+        GeneratedKey {
+            key_type: self.key_type.name(),
+            private_key_file,
+            private_key_keyring: None,
+            public_key_file,
+            mnemonic_file: None,
+            metadata_file: None,
+            algorithm_tag_file: None,
+            checksum_file: None,
+            identity_file: None,
+            identity_blob_file: None,
+            public_key: hex::encode(public_key.to_bytes()),
+            private_key: if self.include_private_key || self.print_private {
+                Some(hex::encode(key.to_bytes()))
+            } else {
+                None
+            },
+            account: self.account_of(public_key),
+        }
+    }
+
+    /// Authentication key and account address of an `ed25519` public key, with
+    /// `--print-address` and the `single` scheme
+    ///
+    /// With the `multi` scheme the keys only have the account of their multi-ed25519 key.
+    fn account_of<PublicKey: ValidCryptoMaterial>(
+        &self,
+        public_key: &PublicKey,
+    ) -> Option<AuthKeyInfo> {
+        if !self.print_address
+            || !matches!(self.key_type, KeyType::Ed25519)
+            || self.scheme() != AuthScheme::Single
+        {
+            return None;
+        }
+        ed25519::Ed25519PublicKey::try_from(public_key.to_bytes().as_slice())
+            .ok()
+            .map(|public_key| AuthKeyInfo::of(&public_key))
+    }
+
+    /// A test friendly typed key generation for x25519 keys.
+    pub async fn generate_x25519(
+        encoding: EncodingType,
+        key_file: &Path,
+    ) -> CliTypedResult<(x25519::PrivateKey, x25519::PublicKey)> {
+        match generate_and_save(KeyType::X25519, encoding, key_file, true)? {
+            GeneratedPrivateKey::X25519(private_key) => {
+                let public_key = private_key.public_key();
+                Ok((private_key, public_key))
+            }
+            _ => Err(CliError::UnexpectedError(
+                "Generated key isn't an x25519 key".to_string(),
+            )),
+        }
+    }
+
+    /// A test friendly typed key generation for e25519 keys.
+    pub async fn generate_ed25519(
+        encoding: EncodingType,
+        key_file: &Path,
+    ) -> CliTypedResult<(ed25519::Ed25519PrivateKey, ed25519::Ed25519PublicKey)> {
+        match generate_and_save(KeyType::Ed25519, encoding, key_file, true)? {
+            GeneratedPrivateKey::Ed25519(private_key) => {
+                let public_key = private_key.public_key();
+                Ok((private_key, public_key))
+            }
+            _ => Err(CliError::UnexpectedError(
+                "Generated key isn't an ed25519 key".to_string(),
+            )),
+        }
+    }
+
+    /// Generates an `Ed25519PrivateKey` without saving it to disk
+    pub fn generate_ed25519_in_memory() -> ed25519::Ed25519PrivateKey {
+        let mut rng = rand::rngs::StdRng::from_entropy();
+        ed25519::Ed25519PrivateKey::generate(&mut rng)
+    }
+
+    /// Generates a `Secp256k1PrivateKey` without saving it to disk
+    pub fn generate_secp256k1_in_memory() -> secp256k1::Secp256k1PrivateKey {
+        let mut rng = rand::rngs::StdRng::from_entropy();
+        secp256k1::Secp256k1PrivateKey::generate(&mut rng)
+    }
+
+    /// Generates a `Secp256r1PrivateKey` without saving it to disk
+    pub fn generate_secp256r1_in_memory() -> secp256r1::Secp256r1PrivateKey {
+        let mut rng = rand::rngs::StdRng::from_entropy();
+        secp256r1::Secp256r1PrivateKey::generate(&mut rng)
+    }
+
+    pub fn generate_x25519_in_memory() -> CliTypedResult<x25519::PrivateKey> {
+        let key = Self::generate_ed25519_in_memory();
+        x25519::PrivateKey::from_ed25519_private_bytes(&key.to_bytes()).map_err(|err| {
+            CliError::UnexpectedError(format!("Failed to convert ed25519 to x25519 {:?}", err))
+        })
+    }
+}
+
+#[derive(Clone, Debug, Parser)]
+pub struct SaveKey {
+    /// Output file name, or `-` to print the keys like `--stdout`
+    #[clap(long, parse(from_os_str), required_unless_present = "stdout")]
+    output_file: Option<PathBuf>,
+    /// Print the private key to stdout and the public key to stderr instead of saving them
+    ///
+    /// BCS, DER and raw keys are written as raw bytes, other encodings as text.
+    #[clap(long, conflicts_with = "output_file")]
+    stdout: bool,
+    /// Encrypt the private key file with a passphrase, which is prompted for unless given with
+    /// `--passphrase-env` or `--passphrase-file`
+    ///
+    /// The public key file is never encrypted.
+    #[clap(long, conflicts_with = "stdout")]
+    encrypt: bool,
+    /// Public key output file name
+    ///
+    /// Defaults to `output_file` with its `.key` or `.priv` extension replaced by `.pub`, or
+    /// with `.pub` appended for any other extension.
+    #[clap(long, parse(from_os_str), conflicts_with = "stdout")]
+    pub_key_file: Option<PathBuf>,
+    /// Directory to save the public key file in, instead of beside `output_file`
+    ///
+    /// The public key file keeps its default name, e.g. `dir/key.pub` for `keys/key.key`.
+    #[clap(long, parse(from_os_str), conflicts_with_all = &["stdout", "pub_key_file"])]
+    public_key_dir: Option<PathBuf>,
+    /// Extension of the public key file in place of `.pub`, e.g. `.public` or `.ed25519.pub`
+    ///
+    /// It must start with a dot, and replaces the `.key` or `.priv` extension of `output_file`
+    /// like `.pub` does.
+    #[clap(
+        long,
+        default_value = ".pub",
+        parse(try_from_str = parse_public_key_extension),
+        conflicts_with_all = &["stdout", "pub_key_file"]
+    )]
+    public_key_extension: String,
+    /// Only save the private key, without a public key file
+    ///
+    /// The public key can be derived from the private key later, e.g. with `--regen-public`.
+    #[clap(
+        long,
+        conflicts_with_all = &["stdout", "pub_key_file", "public_key_dir", "regen_public"]
+    )]
+    no_public_key_file: bool,
+    /// Create the directories of the saved files if they don't exist, e.g. `--public-key-dir`
+    #[clap(long, conflicts_with = "stdout")]
+    create_dirs: bool,
+    /// Only regenerate the public key file of the existing private key file `output_file`
+    ///
+    /// The private key file is read, and never written.
+    #[clap(long, conflicts_with_all = &["stdout", "encrypt"])]
+    regen_public: bool,
+    /// Also save a description of the key to `output_file.meta.json`, for keeping an inventory
+    ///
+    /// The description has the creation time, key type, encoding, and public key, and never
+    /// the private key.
+    #[clap(long, conflicts_with = "stdout")]
+    with_metadata: bool,
+    /// Also save a tag of the key's algorithm to `output_file.alg`
+    ///
+    /// The tag is a versioned line naming the key type, curve, scheme, and encoding, e.g.
+    /// `aptos-key-alg/1 key_type=ed25519 curve=edwards25519 scheme=eddsa encoding=hex`.  Keys
+    /// loaded with `--encoding auto` are read with the encoding of their tag.
+    #[clap(long, conflicts_with = "stdout")]
+    algorithm_agility: bool,
+    /// Also save the hex SHA-256 of the private key file to `output_file.sha256`
+    ///
+    /// Key files with a checksum file are checked against it when they're loaded, to catch
+    /// corruption or tampering.
+    #[clap(long, conflicts_with_all = &["stdout", "regen_public"])]
+    with_checksum: bool,
+    /// Show the public key and the files that would be saved, without saving anything
+    ///
+    /// Fails if a file would be overwritten, unless using `--assume-yes`.
+    #[clap(long, conflicts_with = "stdout")]
+    dry_run: bool,
+    /// Acknowledge that the existing private key of an on-chain account is being replaced
+    ///
+    /// A private key file with an `output_file.address` file beside it belongs to an account,
+    /// and is never overwritten without this, even with `--assume-yes`.
+    #[clap(long, conflicts_with_all = &["stdout", "regen_public"])]
+    rotate_existing: bool,
+    /// Back up existing key files to `file.bak-<unix seconds>` before overwriting them
+    ///
+    /// Only the private and public key files are backed up, and only with `--key-store file`.
+    #[clap(long, conflicts_with = "stdout")]
+    overwrite_backup: bool,
+    /// End text encoded key files with a newline
+    ///
+    /// Only for `hex`, `base64`, `base64url` and `bech32`, binary encodings are never changed
+    /// and PEM always ends with a newline.
+    #[clap(long, conflicts_with = "stdout")]
+    trailing_newline: bool,
+    /// Case of the hex digits of `hex` encoded keys: `upper` or `lower`
+    ///
+    /// Reading hex accepts either case.
+    #[clap(long, default_value = "upper")]
+    hex_case: HexCase,
+    /// SEC1 form of `secp256k1` and `secp256r1` public keys: `compressed` or `uncompressed`
+    ///
+    /// Reading a public key accepts either form.
+    #[clap(long, default_value = "compressed")]
+    secp_format: SecpFormat,
+    /// Leave the `=` padding off `base64` encoded keys
+    ///
+    /// `base64url` keys are never padded, and reading either accepts keys with or without
+    /// padding.
+    #[clap(long)]
+    base64_no_pad: bool,
+    #[clap(flatten)]
+    key_store_options: KeyStoreOptions,
+    #[clap(flatten)]
+    passphrase_options: PassphraseOptions,
+    #[clap(flatten)]
+    prompt_options: PromptOptions,
+    #[clap(flatten)]
+    encoding_options: EncodingOptions,
+}
+
+impl SaveKey {
+    /// Parameters for saving a key to `output_file` and its public key beside it, as without
+    /// any other option
+    pub fn new(
+        output_file: PathBuf,
+        encoding: EncodingType,
+        prompt_options: PromptOptions,
+    ) -> SaveKey {
+        SaveKey {
+            output_file: Some(output_file),
+            stdout: false,
+            encrypt: false,
+            pub_key_file: None,
+            public_key_dir: None,
+            public_key_extension: format!(".{}", PUBLIC_KEY_EXTENSION),
+            no_public_key_file: false,
+            create_dirs: false,
+            regen_public: false,
+            with_metadata: false,
+            algorithm_agility: false,
+            with_checksum: false,
+            dry_run: false,
+            rotate_existing: false,
+            overwrite_backup: false,
+            trailing_newline: false,
+            hex_case: HexCase::Upper,
+            secp_format: SecpFormat::Compressed,
+            base64_no_pad: false,
+            key_store_options: KeyStoreOptions::default(),
+            passphrase_options: PassphraseOptions::default(),
+            prompt_options,
+            encoding_options: EncodingOptions {
+                encoding,
+                bech32_hrp: DEFAULT_BECH32_HRP.to_string(),
+            },
+        }
+    }
+
+    /// Parameters for the key at `index` of a batch, saved to `output_file-index`
+    fn for_index(&self, index: usize) -> CliTypedResult<SaveKey> {
+        let mut output_file = self.output_file()?.as_os_str().to_owned();
+        output_file.push(format!("-{}", index));
+        Ok(SaveKey {
+            output_file: Some(PathBuf::from(output_file)),
+            ..self.clone()
+        })
+    }
+
+    /// Whether the keys are printed instead of saved, with `--stdout` or an output file of `-`
+    pub fn prints_keys(&self) -> bool {
+        self.stdout || self.output_file.as_deref().map_or(false, is_stdout_path)
+    }
+
+    /// Private key file name
+    fn output_file(&self) -> CliTypedResult<&Path> {
+        self.output_file.as_deref().ok_or_else(|| {
+            CliError::CommandArgumentError(
+                "--output-file must be given unless using --stdout".to_string(),
+            )
+        })
+    }
+
+    /// Where the private key is stored, `output_file` unless it's in the keyring
+    fn key_store(&self) -> CliTypedResult<Box<dyn KeyStore>> {
+        Ok(self
+            .key_store_options
+            .key_store(self.output_file()?.to_path_buf()))
+    }
+
+    /// Keyring entry the private key is saved to, e.g. `keyring:aptos/key`, with
+    /// `--key-store keyring`
+    pub fn keyring_location(&self) -> CliTypedResult<Option<String>> {
+        if self.prints_keys() || !self.key_store_options.is_keyring() {
+            return Ok(None);
+        }
+        Ok(Some(self.key_store()?.location()))
+    }
+
+    /// Public key file name
+    fn public_key_file(&self) -> CliTypedResult<PathBuf> {
+        if let Some(ref pub_key_file) = self.pub_key_file {
+            return Ok(pub_key_file.clone());
+        }
+        let public_key_file = public_key_file_with_extension(
+            self.output_file()?,
+            self.public_key_extension.trim_start_matches('.'),
+        )?;
+        match (&self.public_key_dir, public_key_file.file_name()) {
+            (Some(public_key_dir), Some(file_name)) => Ok(public_key_dir.join(file_name)),
+            _ => Ok(public_key_file),
+        }
+    }
+
+    /// Checks the directory of a file to be saved exists, creating it with `--create-dirs`
+    ///
+    /// A dry run never creates it.
+    fn check_parent_dir(&self, file: &Path) -> CliTypedResult<()> {
+        let parent = match file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => parent,
+            _ => return Ok(()),
+        };
+        if !self.create_dirs {
+            return Err(CliError::CommandArgumentError(format!(
+                "Parent directory {} of {} does not exist; pass --create-dirs to create it",
+                parent.display(),
+                file.display()
+            )));
+        }
+        if !self.dry_run {
+            std::fs::create_dir_all(parent)
+                .map_err(|err| CliError::IO(parent.display().to_string(), err))?;
+        }
+        Ok(())
+    }
+
+    /// Checks the directories of the private key, public key, and metadata files exist
+    fn check_key_dirs(&self) -> CliTypedResult<()> {
+        // A key in the keyring has no file
+        if matches!(self.key_store_options.key_store, KeyStoreType::File) {
+            self.check_parent_dir(self.output_file()?)?;
+        }
+        if !self.no_public_key_file {
+            self.check_parent_dir(&self.public_key_file()?)?;
+        }
+        if self.with_metadata {
+            self.check_parent_dir(&self.metadata_file()?)?;
+        }
+        if self.algorithm_agility {
+            self.check_parent_dir(&AlgorithmTag::file(self.output_file()?)?)?;
+        }
+        Ok(())
+    }
+
+    /// Mnemonic file name
+    fn mnemonic_file(&self) -> CliTypedResult<PathBuf> {
+        append_file_extension(self.output_file()?, MNEMONIC_EXTENSION)
+    }
+
+    /// Check if the mnemonic file exists already
+    pub fn check_mnemonic_file(&self) -> CliTypedResult<()> {
+        if self.prints_keys() {
+            return Ok(());
+        }
+        let mnemonic_file = self.mnemonic_file()?;
+        self.check_parent_dir(&mnemonic_file)?;
+        self.check_file(&mnemonic_file)
+    }
+
+    /// Check if a file exists already, which fails a dry run instead of prompting
+    pub fn check_file(&self, file: &Path) -> CliTypedResult<()> {
+        if !self.dry_run {
+            return check_if_file_exists(file, self.prompt_options);
+        }
+        if file.exists() && !self.prompt_options.overwrites() {
+            self.prompt_options
+                .check_no_overwrite(&file.display().to_string())?;
+            return Err(CliError::CommandArgumentError(format!(
+                "{} already exists, pass --assume-yes to overwrite it",
+                file.display()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Saves a mnemonic phrase to a user only file, or prints it to stderr with `--stdout`
+    pub fn save_mnemonic(&self, phrase: &str) -> CliTypedResult<Option<PathBuf>> {
+        if self.prints_keys() {
+            let mut stderr = std::io::stderr();
+            writeln!(stderr, "{}", phrase)
+                .map_err(|err| CliError::IO("mnemonic".to_string(), err))?;
+            return Ok(None);
+        }
+
+        let mnemonic_file = self.mnemonic_file()?;
+        if !self.dry_run {
+            write_to_user_only_file(&mnemonic_file, "mnemonic", phrase.as_bytes())?;
+        }
+        Ok(Some(mnemonic_file))
+    }
+
+    /// Check if the key files exist already, saying which of them do
+    pub fn check_key_file(&self) -> CliTypedResult<()> {
+        // Nothing is written when printing the keys
+        if self.prints_keys() {
+            return Ok(());
+        }
+
+        self.check_key_dirs()?;
+        let key_store = self.key_store()?;
+        let public_key_file = self.public_key_file()?;
+        // Without a public key file, an existing one is left alone
+        let public_key_file_exists = !self.no_public_key_file && public_key_file.exists();
+        match (key_store.exists()?, public_key_file_exists) {
+            (true, _) => {
+                self.check_account_key()?;
+                if !self.prompt_options.overwrites() {
+                    eprintln!(
+                        "Private key {} already exists, use --regen-public to only \
+                        regenerate its public key file",
+                        key_store.location()
+                    );
+                }
+                if self.dry_run && !self.prompt_options.overwrites() {
+                    self.prompt_options
+                        .check_no_overwrite(&key_store.location())?;
+                    return Err(CliError::CommandArgumentError(format!(
+                        "{} already exists, pass --assume-yes to overwrite it",
+                        key_store.location()
+                    )));
+                }
+                if !self.dry_run {
+                    key_store.check_overwrite(self.prompt_options)?;
+                }
+                if public_key_file_exists {
+                    self.check_file(&public_key_file)?;
+                }
+                Ok(())
+            }
+            // Only a public key file is left over e.g. from a partial run, so the private key
+            // is new
+            (false, true) => {
+                if !self.prompt_options.overwrites() {
+                    eprintln!(
+                        "Public key file {} exists without its private key {}",
+                        public_key_file.display(),
+                        key_store.location()
+                    );
+                }
+                self.check_file(&public_key_file)
+            }
+            (false, false) => Ok(()),
+        }?;
+
+        if self.with_metadata {
+            self.check_file(&self.metadata_file()?)?;
+        }
+        if self.algorithm_agility {
+            self.check_file(&AlgorithmTag::file(self.output_file()?)?)?;
+        }
+        if self.with_checksum {
+            // A key in the keyring has no file to check
+            if !matches!(self.key_store_options.key_store, KeyStoreType::File) {
+                return Err(CliError::CommandArgumentError(
+                    "--with-checksum needs --key-store file".to_string(),
+                ));
+            }
+            self.check_file(&self.checksum_file()?)?;
+        }
+        // A key in the keyring has no file to back up
+        if self.overwrite_backup && !matches!(self.key_store_options.key_store, KeyStoreType::File)
+        {
+            return Err(CliError::CommandArgumentError(
+                "--overwrite-backup needs --key-store file".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Backs up an existing key file before it's overwritten, with `--overwrite-backup`
+    fn backup_file(&self, file: &Path) -> CliTypedResult<()> {
+        if !self.overwrite_backup || self.dry_run {
+            return Ok(());
+        }
+        if let Some(backup_file) = backup_existing_file(file)? {
+            eprintln!("Backed up {} to {}", file.display(), backup_file.display());
+        }
+        Ok(())
+    }
+
+    /// Checks an existing private key file isn't the key of an on-chain account, unless
+    /// replacing it is acknowledged with `--rotate-existing`
+    ///
+    /// This is on top of the prompt for overwriting the file, which `--assume-yes` skips.
+    fn check_account_key(&self) -> CliTypedResult<()> {
+        // A key in the keyring has no file to put an address beside
+        if self.rotate_existing || !matches!(self.key_store_options.key_store, KeyStoreType::File) {
+            return Ok(());
+        }
+        let address_file = self.address_file()?;
+        if !address_file.exists() {
+            return Ok(());
+        }
+        Err(CliError::CommandArgumentError(format!(
+            "Private key {} belongs to the account in {}, pass --rotate-existing to replace it",
+            self.output_file()?.display(),
+            address_file.display()
+        )))
+    }
+
+    /// Account address file name
+    fn address_file(&self) -> CliTypedResult<PathBuf> {
+        append_file_extension(self.output_file()?, ADDRESS_EXTENSION)
+    }
+
+    /// Check the private key exists, and the public key file can be written, for
+    /// `--regen-public`
+    pub fn check_public_key_file(&self) -> CliTypedResult<()> {
+        let key_store = self.key_store()?;
+        if !key_store.exists()? {
+            return Err(CliError::CommandArgumentError(format!(
+                "--regen-public needs the existing private key {}",
+                key_store.location()
+            )));
+        }
+        self.check_parent_dir(&self.public_key_file()?)?;
+        self.check_file(&self.public_key_file()?)
+    }
+
+    /// Loads the existing private key
+    pub fn load_private_key<Key: ValidCryptoMaterial + KeyFormat>(&self) -> CliTypedResult<Key> {
+        self.encoding_options.encoding.load_key_from_store(
+            "--output-file",
+            self.key_store()?.as_ref(),
+            &self.passphrase_options,
+            &self.encoding_options.bech32_hrp,
+        )
+    }
+
+    /// Saves a public key to the public key file
+    pub fn save_public_key<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        public_key: &Key,
+        key_name: &'static str,
+    ) -> CliTypedResult<PathBuf> {
+        let public_key_file = self.public_key_file()?;
+        self.backup_file(&public_key_file)?;
+        if !self.dry_run {
+            self.key_file_at(&public_key_file)
+                .save(key_name, public_key)?;
+        }
+        Ok(public_key_file)
+    }
+
+    /// A key file at `path` in the encoding, which saves keys in the forms of `--hex-case`,
+    /// `--secp-format`, `--bech32-hrp` and `--base64-no-pad`, and with `--trailing-newline`
+    pub fn key_file_at(&self, path: impl Into<PathBuf>) -> KeyFile {
+        self.encoding_options
+            .key_file(path)
+            .with_encode_options(self.encode_options())
+            .with_trailing_newline(self.trailing_newline)
+    }
+
+    /// Encodes a key in the encoding, with hex in the `--hex-case` and ECDSA public keys in the
+    /// `--secp-format`
+    fn encode_key<Key: ValidCryptoMaterial + KeyFormat>(
+        &self,
+        key_name: &'static str,
+        key: &Key,
+    ) -> CliTypedResult<Zeroizing<Vec<u8>>> {
+        self.encoding_options
+            .encoding
+            .encode_key_with(key_name, key, self.encode_options())
+    }
+
+    /// Forms of the encoded keys, from `--hex-case`, `--secp-format`, `--bech32-hrp` and
+    /// `--base64-no-pad`
+    fn encode_options(&self) -> KeyEncodeOptions {
+        KeyEncodeOptions {
+            hex_case: self.hex_case,
+            secp_format: self.secp_format,
+            bech32_hrp: self.encoding_options.bech32_hrp.clone(),
+            base64_no_pad: self.base64_no_pad,
+        }
+    }
+
+    /// Saves a key to a file encoded in a string
+    ///
+    /// Returns the public key of the key, and the names of the saved files.
+    pub fn save_key<Key>(
+        &self,
+        key: &Key,
+        key_name: &'static str,
+    ) -> CliTypedResult<(Key::PublicKeyMaterial, HashMap<&'static str, PathBuf>)>
+    where
+        Key: PrivateKey + ValidCryptoMaterial + KeyFormat,
+        Key::PublicKeyMaterial: KeyFormat,
+    {
+        debug!(
+            "Saving {} ({}) as {}",
+            key_name,
+            describe_key::<Key>(),
+            self.encoding_options.encoding
+        );
+        let public_key = key.public_key();
+
+        let mut map = HashMap::new();
+        if self.prints_keys() {
+            let encoded_private_key = self.encode_key(key_name, key)?;
+            let encoded_public_key = self.encode_key(key_name, &public_key)?;
+            self.print_key(std::io::stdout(), key_name, &encoded_private_key)?;
+            self.print_key(std::io::stderr(), key_name, &encoded_public_key)?;
+            return Ok((public_key, map));
+        }
+
+        // Only the private key is encrypted, the public key is left readable
+        let encoded_private_key = self
+            .key_file_at(self.output_file()?)
+            .encode(key_name, key)?;
+        let encoded_private_key = if self.encrypt && !self.dry_run {
+            let passphrase = self.passphrase_options.new_passphrase()?;
+            Zeroizing::new(encryption::encrypt(&encoded_private_key, &passphrase)?)
+        } else {
+            encoded_private_key
+        };
+
+        // Store the private key, and write the public key to a file
+        let key_store = self.key_store()?;
+        if matches!(self.key_store_options.key_store, KeyStoreType::File) {
+            self.backup_file(self.output_file()?)?;
+        }
+        if !self.dry_run {
+            key_store.save(key_name, &encoded_private_key)?;
+        }
+        // A key in the keyring is described by `keyring_location` instead
+        if let Some(path) = key_store.path() {
+            map.insert("PrivateKey Path", path.to_path_buf());
+        }
+        if self.with_checksum {
+            map.insert("Checksum Path", self.save_checksum(&encoded_private_key)?);
+        }
+        if !self.no_public_key_file {
+            map.insert(
+                "PublicKey Path",
+                self.save_public_key(&public_key, key_name)?,
+            );
+        }
+        if self.with_metadata {
+            map.insert("Metadata Path", self.save_metadata(&public_key, key_name)?);
+        }
+        if self.algorithm_agility {
+            map.insert(
+                "Algorithm Tag Path",
+                self.save_algorithm_tag::<Key>(key_name)?,
+            );
+        }
+        Ok((public_key, map))
+    }
+
+    /// Checksum file name
+    fn checksum_file(&self) -> CliTypedResult<PathBuf> {
+        append_file_extension(self.output_file()?, CHECKSUM_EXTENSION)
+    }
+
+    /// Saves the checksum of the private key file, as it's written
+    fn save_checksum(&self, encoded_private_key: &[u8]) -> CliTypedResult<PathBuf> {
+        let checksum_file = self.checksum_file()?;
+        if !self.dry_run {
+            write_to_public_file(
+                &checksum_file,
+                "checksum",
+                checksum(encoded_private_key).as_bytes(),
+            )?;
+        }
+        Ok(checksum_file)
+    }
+
+    /// Network identity file name, for `--network-identity`
+    fn identity_file(&self) -> CliTypedResult<PathBuf> {
+        append_file_extension(self.output_file()?, IDENTITY_EXTENSION)
+    }
+
+    /// Identity blob file name, for `--network-identity` without `--inline-key`
+    fn identity_blob_file(&self) -> CliTypedResult<PathBuf> {
+        append_file_extension(self.output_file()?, IDENTITY_BLOB_EXTENSION)
+    }
+
+    /// Metadata file name
+    fn metadata_file(&self) -> CliTypedResult<PathBuf> {
+        append_file_extension(self.output_file()?, METADATA_EXTENSION)
+    }
+
+    /// Saves the metadata of a key, which only has its public key
+    fn save_metadata<Key: ValidCryptoMaterial>(
+        &self,
+        public_key: &Key,
+        key_name: &'static str,
+    ) -> CliTypedResult<PathBuf> {
+        let created_at_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?
+            .as_secs();
+        let metadata = KeyMetadata {
+            created_at_secs,
+            key_type: key_name.to_string(),
+            encoding: self.encoding_options.encoding.to_string(),
+            public_key: hex::encode(public_key.to_bytes()),
+        };
+        let json = serde_json::to_string_pretty(&metadata)
+            .map_err(|err| CliError::UnexpectedError(err.to_string()))?;
+
+        let metadata_file = self.metadata_file()?;
+        if !self.dry_run {
+            write_to_public_file(&metadata_file, "key metadata", json.as_bytes())?;
+        }
+        Ok(metadata_file)
+    }
+
+    /// Saves the algorithm tag of a `Key`, with `--algorithm-agility`
+    fn save_algorithm_tag<Key: KeyFormat>(
+        &self,
+        key_name: &'static str,
+    ) -> CliTypedResult<PathBuf> {
+        let key_type = Key::KEY_TYPE.ok_or_else(|| {
+            CliError::UnexpectedError(format!("{} keys have no algorithm tag", key_name))
+        })?;
+        let tag = AlgorithmTag {
+            key_type,
+            encoding: self.encoding_options.encoding,
+        };
+
+        let tag_file = AlgorithmTag::file(self.output_file()?)?;
+        if !self.dry_run {
+            write_to_public_file(&tag_file, "algorithm tag", format!("{}\n", tag).as_bytes())?;
+        }
+        Ok(tag_file)
+    }
+
+    /// Prints an encoded key, BCS as raw bytes and anything else as a line of text
+    pub(crate) fn print_key(
+        &self,
+        mut writer: impl Write,
+        key_name: &'static str,
+        encoded_key: &[u8],
+    ) -> CliTypedResult<()> {
+        writer
+            .write_all(encoded_key)
+            .map_err(|err| CliError::IO(key_name.to_string(), err))?;
+        // PEM armor already ends in a newline
+        if !matches!(
+            self.encoding_options.encoding,
+            EncodingType::BCS | EncodingType::Der | EncodingType::Raw | EncodingType::Pem
+        ) {
+            writer
+                .write_all(b"\n")
+                .map_err(|err| CliError::IO(key_name.to_string(), err))?;
+        }
+        writer
+            .flush()
+            .map_err(|err| CliError::IO(key_name.to_string(), err))
     }
 }
@@ -0,0 +1,202 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! BIP39 mnemonics and SLIP-0010 paths for deriving `ed25519` keys
+//!
+//! A mnemonic is turned into a seed as described in BIP39 with an empty passphrase, and the key
+//! is derived from that seed along a SLIP-0010 path.  The empty path `m` is the master key.
+
+use crate::common::types::{CliError, CliTypedResult};
+use aptos_crypto::ed25519::Ed25519PrivateKey;
+use bip39::{Language, Mnemonic, Seed};
+use hmac::{Hmac, Mac, NewMac};
+use rand::{RngCore, SeedableRng};
+use sha2::Sha512;
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+pub const MNEMONIC_EXTENSION: &str = "mnemonic";
+
+/// HMAC key for the SLIP-0010 master key of the `ed25519` curve
+const ED25519_SEED_KEY: &[u8] = b"ed25519 seed";
+
+/// Indices at or above this are hardened
+const HARDENED_OFFSET: u32 = 1 << 31;
+
+/// The standard path of the first Aptos account key
+pub const APTOS_DERIVATION_PATH: &str = "m/44'/637'/0'/0'/0'";
+
+/// Length of an extended key, the private key followed by its chain code
+pub const EXTENDED_KEY_LENGTH: usize = 64;
+
+/// Entropy for a 24 word mnemonic
+const MNEMONIC_ENTROPY_LENGTH: usize = 32;
+
+/// Generates a new random 24 word mnemonic
+pub fn generate_mnemonic() -> CliTypedResult<Mnemonic> {
+    let mut rng = rand::rngs::StdRng::from_entropy();
+    let mut entropy = [0u8; MNEMONIC_ENTROPY_LENGTH];
+    rng.fill_bytes(&mut entropy);
+    Mnemonic::from_entropy(&entropy, Language::English)
+        .map_err(|err| CliError::UnexpectedError(format!("Failed to create mnemonic {}", err)))
+}
+
+/// Parses a mnemonic phrase, failing if any word or the checksum is invalid
+pub fn parse_mnemonic(phrase: &str) -> CliTypedResult<Mnemonic> {
+    Mnemonic::from_phrase(phrase.trim(), Language::English)
+        .map_err(|err| CliError::CommandArgumentError(format!("Invalid mnemonic: {}", err)))
+}
+
+/// Shows a 16 byte key fingerprint as 12 BIP39 words
+///
+/// The words only stand for the fingerprint, they aren't a mnemonic of any key.
+pub fn fingerprint_words(fingerprint: &[u8]) -> CliTypedResult<String> {
+    Mnemonic::from_entropy(fingerprint, Language::English)
+        .map(|mnemonic| mnemonic.into_phrase())
+        .map_err(|err| CliError::UnexpectedError(format!("Invalid fingerprint {}", err)))
+}
+
+/// Derives the `ed25519` key of a mnemonic at a path, the same mnemonic and path always derive
+/// the same key
+pub fn derive_ed25519_key(
+    mnemonic: &Mnemonic,
+    path: &DerivationPath,
+) -> CliTypedResult<Ed25519PrivateKey> {
+    let seed = Seed::new(mnemonic, "");
+    derive_key(seed.as_bytes(), path)
+}
+
+/// Derives the `ed25519` key of a seed at a SLIP-0010 path
+pub fn derive_key(seed: &[u8], path: &DerivationPath) -> CliTypedResult<Ed25519PrivateKey> {
+    let (key, chain_code) = master_key(seed);
+    derive_child_key(key, chain_code, path)
+}
+
+/// Derives the `ed25519` key at a SLIP-0010 path relative to an extended key
+///
+/// The extended key is the private key of `m` followed by its chain code, so the path `m`
+/// derives its own private key.
+pub fn derive_extended_key(
+    extended_key: &[u8],
+    path: &DerivationPath,
+) -> CliTypedResult<Ed25519PrivateKey> {
+    if extended_key.len() != EXTENDED_KEY_LENGTH {
+        return Err(CliError::CommandArgumentError(format!(
+            "Expected a {} byte extended key, the private key followed by its chain code, \
+            found {} bytes",
+            EXTENDED_KEY_LENGTH,
+            extended_key.len()
+        )));
+    }
+    let (key, chain_code) = extended_key.split_at(EXTENDED_KEY_LENGTH / 2);
+    derive_child_key(key.to_vec(), chain_code.to_vec(), path)
+}
+
+/// Derives the hardened children of a key and chain code along a path
+fn derive_child_key(
+    mut key: Vec<u8>,
+    mut chain_code: Vec<u8>,
+    path: &DerivationPath,
+) -> CliTypedResult<Ed25519PrivateKey> {
+    for index in &path.0 {
+        let (child_key, child_chain_code) = child_key(&key, &chain_code, *index);
+        key = child_key;
+        chain_code = child_chain_code;
+    }
+    Ed25519PrivateKey::try_from(key.as_slice())
+        .map_err(|err| CliError::UnexpectedError(format!("Failed to derive key {}", err)))
+}
+
+/// Computes the SLIP-0010 `ed25519` master key and chain code of a seed
+pub fn master_key(seed: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    hmac_sha512(ED25519_SEED_KEY, &[seed])
+}
+
+/// Computes the hardened child key and chain code at `index`, which is already hardened
+fn child_key(key: &[u8], chain_code: &[u8], index: u32) -> (Vec<u8>, Vec<u8>) {
+    hmac_sha512(chain_code, &[&[0u8], key, &index.to_be_bytes()])
+}
+
+/// Splits the HMAC-SHA512 of `data` into the key and chain code halves
+fn hmac_sha512(hmac_key: &[u8], data: &[&[u8]]) -> (Vec<u8>, Vec<u8>) {
+    let mut mac = Hmac::<Sha512>::new_varkey(hmac_key).expect("HMAC can take a key of any size");
+    for bytes in data {
+        mac.update(bytes);
+    }
+    let output = mac.finalize().into_bytes();
+    let (key, chain_code) = output.split_at(32);
+    (key.to_vec(), chain_code.to_vec())
+}
+
+/// A SLIP-0010 derivation path e.g. `m/44'/637'/0'/0'/0'`
+///
+/// Only hardened indices are supported, as `ed25519` can't derive non-hardened children.
+/// Indices are stored with the hardened offset already added.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// The path of the first Aptos account key, [`APTOS_DERIVATION_PATH`]
+    pub fn aptos() -> DerivationPath {
+        APTOS_DERIVATION_PATH
+            .parse()
+            .expect("the Aptos derivation path is valid")
+    }
+
+    /// The path of the hardened child at `index` of the master key, `m/index'`
+    pub fn hardened_child(index: u32) -> CliTypedResult<DerivationPath> {
+        if index >= HARDENED_OFFSET {
+            return Err(CliError::CommandArgumentError(format!(
+                "Invalid index {}: it must be less than {}",
+                index, HARDENED_OFFSET
+            )));
+        }
+        Ok(DerivationPath(vec![index + HARDENED_OFFSET]))
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = CliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| {
+            CliError::CommandArgumentError(format!("Invalid derivation path {}: {}", s, reason))
+        };
+        let mut parts = s.trim().split('/');
+        if parts.next() != Some("m") {
+            return Err(invalid("it must start with 'm'".to_string()));
+        }
+
+        let mut indices = Vec::new();
+        for part in parts {
+            let index = part
+                .strip_suffix(|c| c == '\'' || c == 'h' || c == 'H')
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "index {} isn't hardened, ed25519 only supports hardened indices like {}'",
+                        part, part
+                    ))
+                })?;
+            let index: u32 = index
+                .parse()
+                .map_err(|_| invalid(format!("index {} isn't a number", part)))?;
+            if index >= HARDENED_OFFSET {
+                return Err(invalid(format!(
+                    "index {} must be less than {}",
+                    part, HARDENED_OFFSET
+                )));
+            }
+            indices.push(index + HARDENED_OFFSET);
+        }
+        Ok(DerivationPath(indices))
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            write!(f, "/{}'", index - HARDENED_OFFSET)?;
+        }
+        Ok(())
+    }
+}
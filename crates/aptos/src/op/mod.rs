@@ -1,4 +1,11 @@
 // Copyright (c) Aptos
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod jwk;
 pub mod key;
+pub mod mnemonic;
+pub mod ssh;
+pub mod vanity;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,115 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! OpenSSH encodings of `ed25519` keys
+//!
+//! Public keys are the one line `authorized_keys` format, and private keys the unencrypted
+//! `openssh-key-v1` format described in OpenSSH's `PROTOCOL.key`.  Both are built from the SSH
+//! wire encoding of RFC 4251, where a string is its big endian `u32` length followed by its
+//! bytes.
+
+use aptos_crypto::{ed25519, PrivateKey};
+use rand::{rngs::OsRng, RngCore};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Name of the `ed25519` key type in SSH
+pub const SSH_ED25519_KEY_TYPE: &str = "ssh-ed25519";
+
+const OPENSSH_PRIVATE_KEY_MAGIC: &[u8] = b"openssh-key-v1\0";
+const OPENSSH_PRIVATE_KEY_PEM_LABEL: &str = "OPENSSH PRIVATE KEY";
+/// The private section is padded to the block size of the cipher, 8 when it's unencrypted
+const OPENSSH_BLOCK_SIZE: usize = 8;
+
+/// Parses the comment of an SSH key, which can't hold control characters
+///
+/// The comment ends the one line `authorized_keys` format, so a newline in it would start
+/// another line, which could add a key of its own.
+pub fn parse_comment(comment: &str) -> Result<String, String> {
+    match comment.chars().find(|c| c.is_control()) {
+        Some(c) => Err(format!(
+            "SSH key comments can't hold control characters, found {:?}",
+            c
+        )),
+        None => Ok(comment.to_string()),
+    }
+}
+
+/// Formats a public key as an `authorized_keys` line, e.g. `ssh-ed25519 AAAA... user@host`
+pub fn ssh_public_key(public_key: &ed25519::Ed25519PublicKey, comment: Option<&str>) -> String {
+    let line = format!(
+        "{} {}",
+        SSH_ED25519_KEY_TYPE,
+        base64::encode(ssh_public_key_blob(public_key))
+    );
+    match comment {
+        Some(comment) => format!("{} {}", line, comment),
+        None => line,
+    }
+}
+
+/// SSH wire encoding of a public key, the key type followed by the key
+pub fn ssh_public_key_blob(public_key: &ed25519::Ed25519PublicKey) -> Vec<u8> {
+    let mut blob = Vec::new();
+    put_string(&mut blob, SSH_ED25519_KEY_TYPE.as_bytes());
+    put_string(&mut blob, &public_key.to_bytes());
+    blob
+}
+
+/// Formats a private key as an unencrypted OpenSSH private key file
+pub fn ssh_private_key(
+    private_key: &ed25519::Ed25519PrivateKey,
+    comment: Option<&str>,
+) -> Zeroizing<String> {
+    let public_key = private_key.public_key();
+
+    // OpenSSH keeps the private key as the seed followed by the public key
+    let mut key_pair = Zeroizing::new(Vec::with_capacity(64));
+    key_pair.extend_from_slice(&private_key.to_bytes());
+    key_pair.extend_from_slice(&public_key.to_bytes());
+
+    // The matching check ints show a decrypted key was decrypted correctly.  Buffers holding
+    // the private key are big enough to never be reallocated, leaving no copies behind.
+    let check_int = OsRng.next_u32();
+    let comment = comment.unwrap_or_default();
+    let mut private_section = Zeroizing::new(Vec::with_capacity(256 + comment.len()));
+    private_section.extend_from_slice(&check_int.to_be_bytes());
+    private_section.extend_from_slice(&check_int.to_be_bytes());
+    put_string(&mut private_section, SSH_ED25519_KEY_TYPE.as_bytes());
+    put_string(&mut private_section, &public_key.to_bytes());
+    put_string(&mut private_section, &key_pair);
+    put_string(&mut private_section, comment.as_bytes());
+    let mut padding = 1u8;
+    while private_section.len() % OPENSSH_BLOCK_SIZE != 0 {
+        private_section.push(padding);
+        padding += 1;
+    }
+
+    let mut encoded = Zeroizing::new(Vec::with_capacity(256 + private_section.len()));
+    encoded.extend_from_slice(OPENSSH_PRIVATE_KEY_MAGIC);
+    // Cipher, KDF, and KDF options, none as the key isn't encrypted
+    put_string(&mut encoded, b"none");
+    put_string(&mut encoded, b"none");
+    put_string(&mut encoded, b"");
+    encoded.extend_from_slice(&1u32.to_be_bytes());
+    put_string(&mut encoded, &ssh_public_key_blob(&public_key));
+    put_string(&mut encoded, &private_section);
+
+    let mut pem = pem::Pem {
+        tag: OPENSSH_PRIVATE_KEY_PEM_LABEL.to_string(),
+        contents: encoded.to_vec(),
+    };
+    let armored = Zeroizing::new(pem::encode_config(
+        &pem,
+        pem::EncodeConfig {
+            line_ending: pem::LineEnding::LF,
+        },
+    ));
+    pem.contents.zeroize();
+    armored
+}
+
+/// Appends an SSH wire encoded string
+fn put_string(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    buffer.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(bytes);
+}
@@ -0,0 +1,118 @@
+// Copyright (c) Aptos
+// SPDX-License-Identifier: Apache-2.0
+
+//! Grinding `ed25519` keys for vanity account addresses
+//!
+//! Keys are generated until the address derived from one starts with a hex prefix.  Each hex
+//! digit of the prefix makes a match 16 times less likely, so the search is exponentially slower
+//! for longer prefixes.  A prefix of 6 digits takes around 16 million attempts on average.
+
+use crate::common::types::{CliError, CliTypedResult};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+use aptos_types::{account_address::AccountAddress, transaction::authenticator::AuthenticationKey};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A key whose address starts with the vanity prefix
+pub struct VanityKey {
+    pub private_key: Ed25519PrivateKey,
+    pub account_address: AccountAddress,
+    /// Keys generated before the match was found, across all threads
+    pub attempts: u64,
+    pub elapsed: Duration,
+}
+
+impl VanityKey {
+    /// Keys generated per second
+    pub fn attempts_per_sec(&self) -> f64 {
+        self.attempts as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Parses a vanity prefix as lowercase hex digits, with or without `0x`
+pub fn parse_vanity_prefix(prefix: &str) -> CliTypedResult<String> {
+    let hex = prefix.strip_prefix("0x").unwrap_or(prefix).to_lowercase();
+    if hex.is_empty()
+        || hex.len() > 2 * AccountAddress::LENGTH
+        || !hex.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Err(CliError::CommandArgumentError(format!(
+            "Invalid --vanity-prefix '{}', expected 1 to {} hex digits",
+            prefix,
+            2 * AccountAddress::LENGTH
+        )));
+    }
+    Ok(hex)
+}
+
+/// Generates keys on `threads` threads until one's address starts with `prefix`, trying at most
+/// `max_attempts` keys
+///
+/// `prefix` must be lowercase hex, as from `parse_vanity_prefix`.  Each thread has its own RNG
+/// seeded from the OS.
+pub fn grind_vanity_key(
+    prefix: &str,
+    max_attempts: u64,
+    threads: usize,
+) -> CliTypedResult<VanityKey> {
+    let start = Instant::now();
+    let found = Arc::new(AtomicBool::new(false));
+    let attempts = Arc::new(AtomicU64::new(0));
+
+    let workers: Vec<_> = (0..threads.max(1))
+        .map(|_| {
+            let prefix = prefix.to_string();
+            let found = found.clone();
+            let attempts = attempts.clone();
+            thread::spawn(move || {
+                let mut rng = StdRng::from_entropy();
+                while !found.load(Ordering::Relaxed)
+                    && attempts.fetch_add(1, Ordering::Relaxed) < max_attempts
+                {
+                    let private_key = Ed25519PrivateKey::generate(&mut rng);
+                    let account_address =
+                        AuthenticationKey::ed25519(&private_key.public_key()).derived_address();
+                    if account_address.to_hex().starts_with(&prefix) {
+                        found.store(true, Ordering::Relaxed);
+                        return Some((private_key, account_address));
+                    }
+                }
+                None
+            })
+        })
+        .collect();
+
+    // Threads that lost the race may have found a match too, the first one joined wins
+    let mut matched = None;
+    for worker in workers {
+        let result = worker
+            .join()
+            .map_err(|_| CliError::UnexpectedError("Vanity search thread panicked".to_string()))?;
+        if matched.is_none() {
+            matched = result;
+        }
+    }
+
+    // Each thread counts one attempt too many when it stops
+    let attempts = attempts.load(Ordering::Relaxed).min(max_attempts);
+    match matched {
+        Some((private_key, account_address)) => Ok(VanityKey {
+            private_key,
+            account_address,
+            attempts,
+            elapsed: start.elapsed(),
+        }),
+        None => Err(CliError::UnexpectedError(format!(
+            "No address starting with {} found in {} attempts, raise --vanity-max-attempts or \
+            use a shorter prefix",
+            prefix, max_attempts
+        ))),
+    }
+}